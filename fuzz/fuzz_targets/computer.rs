@@ -0,0 +1,28 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use aoc2019::{ComputerST, Queue, State};
+
+// Programs that never halt are cut off after this many instructions.
+const LIMIT: u64 = 10_000;
+
+fuzz_target!(|data: (Vec<i64>, Vec<i64>)| {
+    let (rom, inputs) = data;
+    let mut inputs = inputs.into_iter();
+
+    let mut computer = ComputerST::new(&rom);
+    computer.set_limit(Some(LIMIT));
+
+    loop {
+        match computer.step() {
+            Ok(State::Done) | Err(_) => break,
+            Ok(State::HasOutput) => {
+                let _ = computer.output_mut().dequeue();
+            }
+            Ok(State::NeedsInput) => match inputs.next() {
+                Some(val) => computer.input_mut().enqueue(val),
+                None => break,
+            },
+        }
+    }
+});
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use aoc2019::Rom;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Rom::from_reader(data);
+});
@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use aoc2019::Rom;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(rom) = Rom::read_binary(data) {
+        // Anything that decodes must encode back to the same bytes
+        let mut buf = Vec::new();
+        rom.write_binary(&mut buf).unwrap();
+        assert_eq!(&buf[..], data);
+    }
+});
@@ -0,0 +1,358 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::reader::Reader;
+use crate::solution::solution;
+
+/// Known answers for a collection of puzzle inputs, as read from an answers
+/// file.
+///
+/// Answers files consist of `[name]` headers, where `name` is the input's
+/// file name, each followed by `part = answer` lines. The day is taken from
+/// the `dayNN` prefix of the name, so several people's inputs can sit side by
+/// side as `day01.txt`, `day01-alice.txt` and so on. Answers that span lines
+/// or contain a `#` are written as double-quoted strings with `\n`, `\"` and
+/// `\\` escapes. Blank lines and anything after a `#` are ignored.
+///
+/// ```text
+/// [day01.txt]
+/// 1 = 3490763
+/// 2 = 5233250
+///
+/// [day08.txt]
+/// 1 = 1792
+/// 2 = "# #\n###\n"
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Answers(Vec<(String, [Option<String>; 2])>);
+
+impl Answers {
+    pub fn from_reader<R>(reader: R) -> Result<Self, Error>
+    where
+        R: std::io::BufRead,
+    {
+        let mut answers = Answers::default();
+
+        for (i, res) in reader.lines().enumerate() {
+            let line = res?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                let line = strip_comment(line);
+                if !line.ends_with(']') {
+                    bail!("Unterminated header on line {}: {:?}", i + 1, line);
+                }
+                let name = line[1..line.len() - 1].trim();
+                day(name).map_err(|e| error!("{} (line {})", e, i + 1))?;
+                if answers.0.iter().any(|(other, _)| other == name) {
+                    bail!("Duplicate input {:?} on line {}.", name, i + 1);
+                }
+                answers.0.push((name.to_string(), [None, None]));
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let (part, answer) = match (parts.next(), parts.next()) {
+                (Some(part), Some(answer)) => (part.trim(), answer.trim()),
+                _ => bail!("Expected `part = answer` on line {}: {:?}", i + 1, line),
+            };
+            let part = match part {
+                "1" => 0,
+                "2" => 1,
+                _ => bail!("Part must be 1 or 2 on line {}, but got {:?}.", i + 1, part),
+            };
+            let answer = if answer.starts_with('"') {
+                unquote(answer).map_err(|e| error!("{} (line {})", e, i + 1))?
+            } else {
+                strip_comment(answer).to_string()
+            };
+
+            match answers.0.last_mut() {
+                Some((_, slots)) if slots[part].is_some() => {
+                    bail!("Duplicate answer for part {} on line {}.", part + 1, i + 1)
+                }
+                Some((_, slots)) => slots[part] = Some(answer),
+                None => bail!("Answer on line {} comes before any `[name]` header.", i + 1),
+            }
+        }
+
+        Ok(answers)
+    }
+
+    /// Returns the stored answer for `part` (1 or 2) of the input `name`.
+    pub fn get(&self, name: &str, part: usize) -> Option<&str> {
+        let slots = self
+            .0
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, slots)| slots)?;
+        match part {
+            1 | 2 => slots[part - 1].as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn insert<S>(&mut self, name: S, part: usize, answer: String) -> Result<(), Error>
+    where
+        S: Into<String>,
+    {
+        let name = name.into();
+        if part != 1 && part != 2 {
+            bail!("Part must be 1 or 2, but got {}.", part);
+        }
+        day(&name)?;
+
+        match self.0.iter_mut().find(|(other, _)| *other == name) {
+            Some((_, slots)) => slots[part - 1] = Some(answer),
+            None => {
+                let mut slots = [None, None];
+                slots[part - 1] = Some(answer);
+                self.0.push((name, slots));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Iterates over `(name, day)` for every input with at least one answer.
+    pub fn inputs(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.0
+            .iter()
+            .filter(|(_, slots)| slots.iter().any(Option::is_some))
+            .map(|(name, _)| (name.as_str(), day(name).unwrap()))
+    }
+
+    /// Solves every input in `dir` that has answers, optionally only those
+    /// for one `day`, and compares the results, returning one `Check` per
+    /// stored answer.
+    pub fn check<P>(&self, dir: P, day: Option<usize>) -> Vec<Check>
+    where
+        P: AsRef<Path>,
+    {
+        let mut checks = Vec::new();
+
+        for (name, day) in self
+            .inputs()
+            .filter(|&(_, d)| day.is_none() || day == Some(d))
+        {
+            let actual = solve(dir.as_ref(), name, day);
+
+            for part in 1..=2 {
+                let expected = match self.get(name, part) {
+                    Some(expected) => expected.to_string(),
+                    None => continue,
+                };
+                let actual = match &actual {
+                    Ok(answers) => Ok(answers[part - 1].clone()),
+                    Err(e) => Err(error!("{}", e)),
+                };
+                checks.push(Check {
+                    name: name.to_string(),
+                    day,
+                    part,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        checks
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, slots)) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{}]", name)?;
+            for (part, answer) in slots.iter().enumerate() {
+                if let Some(answer) = answer {
+                    writeln!(f, "{} = {}", part + 1, quote(answer))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of comparing one part of one input against its stored answer.
+#[derive(Debug)]
+pub struct Check {
+    pub name: String,
+    pub day: usize,
+    pub part: usize,
+    pub expected: String,
+    pub actual: Result<String, Error>,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        match &self.actual {
+            Ok(actual) => *actual == self.expected,
+            Err(_) => false,
+        }
+    }
+}
+
+fn solve(dir: &Path, name: &str, day: usize) -> Result<[String; 2], Error> {
+    let mut reader = Reader::open(dir.join(name))?;
+    let (answer1, answer2) = solution(day)?.run(&mut reader)?;
+    Ok([answer1, answer2])
+}
+
+/// Returns the day of an input named `dayNN...`.
+fn day(name: &str) -> Result<usize, Error> {
+    let digits = name
+        .strip_prefix("day")
+        .map(|rest| rest.bytes().take_while(u8::is_ascii_digit).count())
+        .unwrap_or(0);
+    if digits == 0 {
+        bail!("Input name {:?} does not start with `dayNN`.", name);
+    }
+    let day = name[3..3 + digits].parse::<usize>()?;
+    if !(1..=25).contains(&day) {
+        bail!(
+            "Input name {:?} refers to day {}, which does not exist.",
+            name,
+            day
+        );
+    }
+    Ok(day)
+}
+
+fn strip_comment(s: &str) -> &str {
+    match s.find('#') {
+        Some(n) => s[..n].trim(),
+        None => s,
+    }
+}
+
+fn quote(answer: &str) -> String {
+    let bare = !answer.is_empty()
+        && answer.trim() == answer
+        && !answer.starts_with('"')
+        && !answer.contains(['\n', '#']);
+    if bare {
+        return answer.to_string();
+    }
+
+    let mut s = String::with_capacity(answer.len() + 2);
+    s.push('"');
+    for c in answer.chars() {
+        match c {
+            '\n' => s.push_str("\\n"),
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+fn unquote(s: &str) -> Result<String, Error> {
+    let mut answer = String::new();
+    let mut chars = s[1..].chars();
+
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some('n') => answer.push('\n'),
+                Some('"') => answer.push('"'),
+                Some('\\') => answer.push('\\'),
+                Some(c) => bail!("Unknown escape `\\{}` in answer.", c),
+                None => bail!("Unterminated answer {:?}.", s),
+            },
+            Some(c) => answer.push(c),
+            None => bail!("Unterminated answer {:?}.", s),
+        }
+    }
+
+    let rest = chars.as_str().trim();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        bail!("Unexpected {:?} after quoted answer.", rest);
+    }
+
+    Ok(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils;
+
+    #[test]
+    fn test_answers() {
+        let input = "# Answers\n[day01.txt]\n1 = 12 # part one\n2 = 34\n\n[day08-alice.txt]\n2 = \"# #\\n\\\"\\\\\\n\"\n";
+        let reader = std::io::BufReader::new(input.as_bytes());
+        let answers = Answers::from_reader(reader).unwrap();
+
+        assert_eq!(answers.get("day01.txt", 1), Some("12"));
+        assert_eq!(answers.get("day01.txt", 2), Some("34"));
+        assert_eq!(answers.get("day08-alice.txt", 1), None);
+        assert_eq!(answers.get("day08-alice.txt", 2), Some("# #\n\"\\\n"));
+        assert_eq!(
+            answers.inputs().collect::<Vec<_>>(),
+            vec![("day01.txt", 1), ("day08-alice.txt", 8)]
+        );
+
+        let output = answers.to_string();
+        let reader = std::io::BufReader::new(output.as_bytes());
+        assert_eq!(Answers::from_reader(reader).unwrap(), answers);
+
+        let bad_inputs = &[
+            "1 = 2",
+            "[input.txt]",
+            "[day26.txt]",
+            "[day01.txt]\n3 = 2",
+            "[day01.txt]\n1 2",
+            "[day01.txt]\n1 = 2\n1 = 3",
+            "[day01.txt]\n1 = \"2",
+            "[day01.txt]\n1 = \"2\" 3",
+            "[day01.txt]\n[day01.txt]",
+        ];
+        for input in bad_inputs {
+            let reader = std::io::BufReader::new(input.as_bytes());
+            assert!(Answers::from_reader(reader).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_answers_file() {
+        let file = std::fs::File::open("input/answers.txt").unwrap();
+        let reader = std::io::BufReader::new(file);
+        let answers = Answers::from_reader(reader).unwrap();
+
+        for (name, day) in answers.inputs() {
+            assert!(crate::solution(day).is_ok(), "{}: no solution", name);
+            let path = std::path::Path::new("input").join(name);
+            assert!(path.is_file(), "{}: no such input", name);
+        }
+        for solution in crate::solutions() {
+            assert!(
+                answers.inputs().any(|(_, day)| day == solution.day()),
+                "No answers for day {}.",
+                solution.day()
+            );
+        }
+    }
+
+    // Each day's own test already checks its answers, so this only needs
+    // running to check inputs that have no test of their own.
+    #[test]
+    #[ignore]
+    fn test_answers_solved() {
+        for solution in crate::solutions() {
+            utils::tests::test_answers(solution.day());
+        }
+    }
+}
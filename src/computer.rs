@@ -3,10 +3,15 @@ use std::convert::TryFrom;
 use crossbeam::channel::{self, Receiver, Sender};
 
 use crate::error::Error;
+use crate::patch::Patch;
 
 pub type ComputerST = Computer<std::collections::VecDeque<i64>>;
 pub type ComputerMT = Computer<Channel<i64>>;
 
+/// Largest number of words a computer's RAM may grow to. Writes past this
+/// address are reported as errors instead of attempting a huge allocation.
+pub const MAX_RAM: u64 = 1 << 20;
+
 #[derive(Clone, Debug)]
 pub struct Computer<Q> {
     /// Program counter
@@ -18,6 +23,10 @@ pub struct Computer<Q> {
     state: StateInternal,
     input: Q,
     output: Q,
+    /// Number of instructions executed so far
+    count: u64,
+    /// Maximum number of instructions to execute before erroring
+    limit: Option<u64>,
 }
 
 impl ComputerST {
@@ -32,8 +41,26 @@ impl ComputerST {
             state: StateInternal::Executing,
             input: std::collections::VecDeque::default(),
             output: std::collections::VecDeque::default(),
+            count: 0,
+            limit: None,
         }
     }
+
+    /// Restarts the computer with `rom` loaded, as if newly created, but
+    /// reusing the memory already allocated. The instruction limit is kept.
+    pub fn reset<R>(&mut self, rom: R)
+    where
+        R: AsRef<[i64]>,
+    {
+        self.pc = 0;
+        self.rb = 0;
+        self.ram.clear();
+        self.ram.extend_from_slice(rom.as_ref());
+        self.state = StateInternal::Executing;
+        self.input.clear();
+        self.output.clear();
+        self.count = 0;
+    }
 }
 
 impl ComputerMT {
@@ -48,6 +75,8 @@ impl ComputerMT {
             state: StateInternal::Executing,
             input,
             output,
+            count: 0,
+            limit: None,
         }
     }
 }
@@ -71,12 +100,19 @@ where
             match self.state {
                 StateInternal::Done => return Ok(State::Done),
                 StateInternal::Executing => {
+                    if let Some(limit) = self.limit {
+                        if self.count >= limit {
+                            bail!("Exceeded instruction limit of {}.", limit);
+                        }
+                    }
+                    self.count += 1;
+
                     let instruction = self.read_instruction()?;
-                    self.execute_instruction(instruction);
+                    self.execute_instruction(instruction)?;
                 }
                 StateInternal::NeedsInput { w } => match self.input.dequeue() {
                     Ok(val) => {
-                        self.ram.write(w, val);
+                        self.ram.write(w, val)?;
                         self.state = StateInternal::Executing;
                     }
                     Err(_) => return Ok(State::NeedsInput),
@@ -137,22 +173,28 @@ where
         Ok(instruction)
     }
 
-    fn execute_instruction(&mut self, instruction: Instruction) {
+    fn execute_instruction(&mut self, instruction: Instruction) -> Result<(), Error> {
         match instruction {
             Instruction::Add { a, b, w } => {
-                self.ram.write(w, a + b);
+                let val = a
+                    .checked_add(b)
+                    .ok_or_else(|| error!("Overflow adding {} and {}.", a, b))?;
+                self.ram.write(w, val)?;
             }
             Instruction::Multiply { a, b, w } => {
-                self.ram.write(w, a * b);
+                let val = a
+                    .checked_mul(b)
+                    .ok_or_else(|| error!("Overflow multiplying {} and {}.", a, b))?;
+                self.ram.write(w, val)?;
             }
             Instruction::Input { w } => {
                 self.state = StateInternal::NeedsInput { w };
-                return;
+                return Ok(());
             }
             Instruction::Output { a } => {
                 self.output.enqueue(a);
                 self.state = StateInternal::HasOutput;
-                return;
+                return Ok(());
             }
             Instruction::JumpIfTrue { a, p } => {
                 if a != 0 {
@@ -166,27 +208,37 @@ where
             }
             Instruction::LessThan { a, b, w } => {
                 if a < b {
-                    self.ram.write(w, 1);
+                    self.ram.write(w, 1)?;
                 } else {
-                    self.ram.write(w, 0);
+                    self.ram.write(w, 0)?;
                 }
             }
             Instruction::Equals { a, b, w } => {
                 if a == b {
-                    self.ram.write(w, 1);
+                    self.ram.write(w, 1)?;
                 } else {
-                    self.ram.write(w, 0);
+                    self.ram.write(w, 0)?;
                 }
             }
             Instruction::RelativeBase { a } => {
-                self.rb += a;
+                self.rb = self.rb.checked_add(a).ok_or_else(|| {
+                    error!("Overflow adjusting relative base {} by {}.", self.rb, a)
+                })?;
             }
             Instruction::Halt => {
                 self.state = StateInternal::Done;
-                return;
+                return Ok(());
             }
         }
         self.state = StateInternal::Executing;
+
+        Ok(())
+    }
+
+    /// Limits the number of instructions executed before `step` and `run`
+    /// return an error, so that programs which never halt can be bounded.
+    pub fn set_limit(&mut self, limit: Option<u64>) {
+        self.limit = limit;
     }
 
     pub fn input_mut(&mut self) -> &mut Q {
@@ -206,47 +258,16 @@ where
         self.ram.read(ptr)
     }
 
-    pub fn write(&mut self, ptr: u64, val: i64) {
+    pub fn write(&mut self, ptr: u64, val: i64) -> Result<(), Error> {
         self.ram.write(ptr, val)
     }
-}
-
-#[derive(Clone, Debug)]
-pub struct Rom(Vec<i64>);
-
-impl Rom {
-    pub fn from_reader<R>(mut reader: R) -> Result<Self, Error>
-    where
-        R: std::io::BufRead,
-    {
-        let mut buf = String::new();
-        reader.read_to_string(&mut buf)?;
-        let vec = buf
-            .trim()
-            .split(',')
-            .map(|s| s.trim().parse::<i64>().map_err(Error::from))
-            .collect::<Result<Vec<_>, Error>>()?;
-        Ok(Rom(vec))
-    }
-}
-
-impl AsRef<[i64]> for Rom {
-    fn as_ref(&self) -> &[i64] {
-        &self.0
-    }
-}
-
-impl std::ops::Deref for Rom {
-    type Target = [i64];
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
 
-impl std::ops::DerefMut for Rom {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+    /// Writes every edit in `patch` to memory.
+    pub fn patch(&mut self, patch: &Patch) -> Result<(), Error> {
+        for &(ptr, val) in patch.edits() {
+            self.ram.write(ptr, val)?;
+        }
+        Ok(())
     }
 }
 
@@ -292,7 +313,7 @@ impl Queue for Channel<i64> {
 }
 
 #[derive(Debug, Eq, PartialEq)]
-enum Mode {
+pub(crate) enum Mode {
     Position,
     Immediate,
     Relative,
@@ -312,31 +333,32 @@ impl TryFrom<u64> for Mode {
     }
 }
 
-struct Modes(u64);
+pub(crate) struct Modes(pub(crate) u64);
+
+impl Modes {
+    /// Returns the mode of the next parameter. Once the high digits run out,
+    /// every remaining parameter is in `Position` mode.
+    pub(crate) fn next_mode(&mut self) -> Result<Mode, Error> {
+        let mode = Mode::try_from(self.0 % 10)?;
+        self.0 /= 10;
+
+        Ok(mode)
+    }
+}
 
 impl Iterator for Modes {
     type Item = Result<Mode, Error>;
 
+    /// Never returns `None`.
     fn next(&mut self) -> Option<Self::Item> {
-        match self.0 {
-            0 => Some(Ok(Mode::Position)),
-            _ => {
-                let mode = match Mode::try_from(self.0 % 10) {
-                    Ok(mode) => mode,
-                    Err(e) => return Some(Err(e)),
-                };
-                self.0 /= 10;
-
-                Some(Ok(mode))
-            }
-        }
+        Some(self.next_mode())
     }
 }
 
 trait Memory {
-    fn read(&mut self, ptr: u64) -> i64;
+    fn read(&self, ptr: u64) -> i64;
 
-    fn write(&mut self, ptr: u64, val: i64);
+    fn write(&mut self, ptr: u64, val: i64) -> Result<(), Error>;
 
     fn read_opcode(&mut self, pc: &mut u64) -> Result<(u64, Modes), Error> {
         let n = self.read(*pc);
@@ -358,12 +380,12 @@ trait Memory {
         let mut val = self.read(*pc);
         *pc += 1;
 
-        let mode = modes.next().unwrap()?;
+        let mode = modes.next_mode()?;
         match mode {
             Mode::Immediate => Ok(val),
             Mode::Position | Mode::Relative => {
                 if mode == Mode::Relative {
-                    val += rb;
+                    val = offset(val, rb)?;
                 }
                 if val < 0 {
                     bail!(
@@ -391,10 +413,10 @@ trait Memory {
         let val = self.read(*pc);
         *pc += 1;
 
-        let mode = modes.next().unwrap()?;
+        let mode = modes.next_mode()?;
         let val2 = match mode {
             Mode::Immediate | Mode::Position => val,
-            Mode::Relative => val + rb,
+            Mode::Relative => offset(val, rb)?,
         };
 
         if val2 < 0 {
@@ -408,22 +430,38 @@ trait Memory {
     }
 }
 
+fn offset(val: i64, rb: i64) -> Result<i64, Error> {
+    val.checked_add(rb)
+        .ok_or_else(|| error!("Overflow offsetting {} by relative base {}.", val, rb))
+}
+
 impl Memory for Vec<i64> {
-    fn read(&mut self, ptr: u64) -> i64 {
-        if ptr as usize >= self.len() {
-            self.resize(ptr as usize + 1, 0);
+    fn read(&self, ptr: u64) -> i64 {
+        // Memory beyond the end of RAM is implicitly zero, so there is no need
+        // to grow the vector just to read from it.
+        match usize::try_from(ptr) {
+            Ok(ptr) => self.get(ptr).copied().unwrap_or(0),
+            Err(_) => 0,
         }
-
-        *self.get(ptr as usize).unwrap()
     }
 
-    fn write(&mut self, ptr: u64, val: i64) {
-        if ptr as usize >= self.len() {
-            self.resize(ptr as usize + 1, 0);
+    fn write(&mut self, ptr: u64, val: i64) -> Result<(), Error> {
+        if ptr >= MAX_RAM {
+            bail!(
+                "Attempted to write to address {}, which exceeds the maximum of {}.",
+                ptr,
+                MAX_RAM - 1
+            );
+        }
+
+        let ptr = ptr as usize;
+        if ptr >= self.len() {
+            self.resize(ptr + 1, 0);
         }
 
-        let reference = self.get_mut(ptr as usize).unwrap();
-        *reference = val;
+        self[ptr] = val;
+
+        Ok(())
     }
 }
 
@@ -477,6 +515,8 @@ enum StateInternal {
 mod tests {
     use super::*;
 
+    use crate::rom::Rom;
+
     #[test]
     fn test_computer() {
         let test_cases = &[
@@ -497,8 +537,8 @@ mod tests {
             let reader = std::io::BufReader::new(input.as_bytes());
             let rom = Rom::from_reader(reader).unwrap();
             let mut computer = ComputerST::new(&rom);
-            computer.write(1, *noun);
-            computer.write(2, *verb);
+            computer.write(1, *noun).unwrap();
+            computer.write(2, *verb).unwrap();
             computer.run().unwrap();
 
             let expected_ram = expected_ram
@@ -509,4 +549,38 @@ mod tests {
             assert_eq!(computer.ram(), &expected_ram[..]);
         }
     }
+
+    #[test]
+    fn test_computer_errors() {
+        let test_cases = &[
+            // Write far past the end of RAM
+            "1,0,0,9223372036854775807,99",
+            // Overflowing addition
+            "1101,9223372036854775807,1,0,99",
+            // Overflowing multiplication
+            "1102,9223372036854775807,2,0,99",
+            // Overflowing relative base
+            "109,9223372036854775807,109,1,99",
+            // Negative pointer
+            "1,-1,0,0,99",
+            // Bad addressing mode
+            "301,0,0,0,99",
+            // Bad opcode
+            "42,99",
+        ];
+
+        for input in test_cases {
+            let reader = std::io::BufReader::new(input.as_bytes());
+            let rom = Rom::from_reader(reader).unwrap();
+            let mut computer = ComputerST::new(&rom);
+            assert!(computer.run().is_err(), "{}", input);
+        }
+
+        // Infinite loop is stopped by the instruction limit
+        let reader = std::io::BufReader::new("1105,1,0".as_bytes());
+        let rom = Rom::from_reader(reader).unwrap();
+        let mut computer = ComputerST::new(&rom);
+        computer.set_limit(Some(1_000));
+        assert!(computer.run().is_err());
+    }
 }
@@ -1,13 +1,23 @@
 use crate::error::Error;
 
-pub fn run<R>(mut input: R) -> Result<(String, String), Error>
+/// Module masses
+pub type Input = Vec<usize>;
+
+pub fn run<R>(input: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    let mut buffer = String::new();
+    let input = parse(input)?;
+
+    Ok((part_one(&input)?, part_two(&input)?))
+}
 
-    let mut total1 = 0;
-    let mut total2 = 0;
+pub fn parse<R>(mut input: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    let mut buffer = String::new();
+    let mut masses = Vec::new();
 
     // // does the same as the loop but this has more resource allocations.
     // // a new string is allocated every time we run through this for loop,
@@ -17,8 +27,7 @@ where
     //     let line = res?;
     //     let num = line.parse::<usize>()?;
     //
-    //     total1 += part_one(num);
-    //     total2 += part_two(num);
+    //     masses.push(num);
     // }
 
     loop {
@@ -27,24 +36,31 @@ where
         }
 
         let num = buffer.trim().parse::<usize>()?;
-
-        total1 += part_one(num);
-        total2 += part_two(num);
+        masses.push(num);
 
         buffer.clear();
     }
 
-    Ok((total1.to_string(), total2.to_string()))
+    Ok(masses)
 }
 
-fn part_one(num: usize) -> usize {
-    match (num / 3).checked_sub(2) {
-        Some(m) => m,
-        None => 0,
-    }
+pub fn part_one(masses: &Input) -> Result<String, Error> {
+    let total = masses.iter().map(|&num| fuel(num)).sum::<usize>();
+
+    Ok(total.to_string())
+}
+
+pub fn part_two(masses: &Input) -> Result<String, Error> {
+    let total = masses.iter().map(|&num| total_fuel(num)).sum::<usize>();
+
+    Ok(total.to_string())
+}
+
+fn fuel(num: usize) -> usize {
+    (num / 3).saturating_sub(2)
 }
 
-fn part_two(mut num: usize) -> usize {
+fn total_fuel(mut num: usize) -> usize {
     let mut total = 0;
     loop {
         let m = match (num / 3).checked_sub(2) {
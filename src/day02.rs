@@ -1,44 +1,100 @@
-use crate::computer::{ComputerST, Rom};
+use crate::computer::ComputerST;
 use crate::error::Error;
+use crate::patch::Patch;
+use crate::rom::Rom;
+use crate::symbolic::{self, SymbolicComputer};
+
+const TARGET: i64 = 19_690_720;
+
+pub type Input = Rom;
 
 pub fn run<R>(input: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    let rom = Rom::from_reader(input)?;
+    let rom = parse(input)?;
+
+    Ok((part_one(&rom)?, part_two(&rom)?))
+}
+
+pub fn parse<R>(input: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Rom::from_reader(input)
+}
+
+pub fn part_one(rom: &Input) -> Result<String, Error> {
+    let mut computer = ComputerST::new(rom);
+    computer.patch(&program_alarm(12, 2))?;
+    computer.run()?;
 
-    let mut computer = ComputerST::new(&rom);
-    computer.write(1, 12);
-    computer.write(2, 2);
+    Ok(computer.read(0).to_string())
+}
+
+pub fn part_two(rom: &Input) -> Result<String, Error> {
+    // Fall back to trying every noun and verb if the program can't be
+    // executed symbolically.
+    let (noun, verb) = match solve(rom) {
+        Ok(solution) => solution,
+        Err(_) => search(rom)?,
+    }
+    .ok_or_else(|| {
+        error!(
+            "Invalid input. Unable to find noun/verb combination that outputs {}.",
+            TARGET
+        )
+    })?;
+
+    Ok((100 * noun + verb).to_string())
+}
+
+/// Finds the noun and verb by evaluating address 0 as an expression in
+/// addresses 1 and 2 and solving for `TARGET`.
+fn solve(rom: &Rom) -> Result<Option<(i64, i64)>, Error> {
+    let mut computer = SymbolicComputer::new(rom, &[1, 2])?;
     computer.run()?;
+    let expr = computer
+        .read(0)
+        .ok_or_else(|| error!("Address 0 depends on the noun and verb as pointers."))?;
 
-    let answer1 = computer.read(0);
+    let patch = symbolic::solve(&expr, TARGET, &[(1, 0..=99), (2, 0..=99)])?;
+    Ok(patch.map(|patch| (patch.edits()[0].1, patch.edits()[1].1)))
+}
 
-    let mut answer2 = Err(error!(
-        "Invalid input. Unable to find noun/verb combination that outputs 19690720."
-    ));
-    'outer: for noun in 0..=99 {
+/// Finds the noun and verb by running the program with every combination.
+fn search(rom: &Rom) -> Result<Option<(i64, i64)>, Error> {
+    for noun in 0..=99 {
         for verb in 0..=99 {
-            let mut computer = ComputerST::new(&rom);
-            computer.write(1, noun);
-            computer.write(2, verb);
+            let mut computer = ComputerST::new(rom);
+            computer.patch(&program_alarm(noun, verb))?;
             computer.run()?;
 
-            if computer.read(0) == 19_690_720 {
-                answer2 = Ok(100 * noun + verb);
-                break 'outer;
+            if computer.read(0) == TARGET {
+                return Ok(Some((noun, verb)));
             }
         }
     }
 
-    Ok((answer1.to_string(), answer2?.to_string()))
+    Ok(None)
+}
+
+/// Sets the noun and verb at addresses 1 and 2. The puzzle's "1202 program
+/// alarm" state is `program_alarm(12, 2)`.
+pub fn program_alarm(noun: i64, verb: i64) -> Patch {
+    Patch::new(vec![(1, noun), (2, verb)])
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_day02() {
+        let file = std::fs::File::open("input/day02.txt").unwrap();
+        let rom = Rom::from_reader(std::io::BufReader::new(file)).unwrap();
+        assert_eq!(solve(&rom).unwrap(), search(&rom).unwrap());
+
         crate::utils::tests::test_full_problem(2, run, "3654868", "7014");
     }
 }
@@ -231,7 +231,23 @@ fn manhattan_distance(a: Point, b: Point) -> u64 {
     ((a.x - b.x).abs() + (a.y - b.y).abs()) as u64
 }
 
+pub type Input = Wires;
+
+/// Every point where the two wires cross.
+pub struct Wires {
+    intersections: Vec<Intersection>,
+}
+
 pub fn run<R>(input: R) -> Result<(String, String), Error>
+where
+    R: std::io::BufRead,
+{
+    let wires = parse(input)?;
+
+    Ok((part_one(&wires)?, part_two(&wires)?))
+}
+
+pub fn parse<R>(input: R) -> Result<Input, Error>
 where
     R: std::io::BufRead,
 {
@@ -250,24 +266,29 @@ where
         bail!("Unable to find any intersections.")
     }
 
-    let (mut min_dist, mut min_steps) = (std::u64::MAX, std::u64::MAX);
-    for intersection in intersections {
-        let point = intersection.point;
-        let steps = intersection.steps;
+    Ok(Wires { intersections })
+}
 
-        // Part 1
-        let dist = manhattan_distance(point, ORIGIN);
-        if dist < min_dist {
-            min_dist = dist;
-        }
+pub fn part_one(wires: &Input) -> Result<String, Error> {
+    let min_dist = wires
+        .intersections
+        .iter()
+        .map(|intersection| manhattan_distance(intersection.point, ORIGIN))
+        .min()
+        .ok_or_else(|| error!("Unable to find any intersections."))?;
 
-        // Part 2
-        if steps < min_steps {
-            min_steps = steps;
-        }
-    }
+    Ok(min_dist.to_string())
+}
+
+pub fn part_two(wires: &Input) -> Result<String, Error> {
+    let min_steps = wires
+        .intersections
+        .iter()
+        .map(|intersection| intersection.steps)
+        .min()
+        .ok_or_else(|| error!("Unable to find any intersections."))?;
 
-    Ok((min_dist.to_string(), min_steps.to_string()))
+    Ok(min_steps.to_string())
 }
 
 #[cfg(test)]
@@ -7,27 +7,47 @@ enum PreviousDigits {
     ThreeOrMore(u8),
 }
 
+/// Inclusive range of candidate passwords
+pub type Input = (usize, usize);
+
 pub fn run<R>(input: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    let (low, high) = read_input(input)?;
+    let range = parse(input)?;
 
-    let (mut answer1, mut answer2) = (0, 0);
+    Ok((part_one(&range)?, part_two(&range)?))
+}
 
-    for n in low..=high {
-        let is_valid = is_valid(n)?;
+pub fn parse<R>(input: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    read_input(input)
+}
 
-        if is_valid.0 {
-            answer1 += 1;
+pub fn part_one(&(low, high): &Input) -> Result<String, Error> {
+    let mut answer = 0;
+
+    for n in low..=high {
+        if is_valid(n)?.0 {
+            answer += 1;
         }
+    }
 
-        if is_valid.1 {
-            answer2 += 1;
+    Ok(answer.to_string())
+}
+
+pub fn part_two(&(low, high): &Input) -> Result<String, Error> {
+    let mut answer = 0;
+
+    for n in low..=high {
+        if is_valid(n)?.1 {
+            answer += 1;
         }
     }
 
-    Ok((answer1.to_string(), answer2.to_string()))
+    Ok(answer.to_string())
 }
 
 fn is_valid(n: usize) -> Result<(bool, bool), Error> {
@@ -82,7 +102,7 @@ fn is_valid(n: usize) -> Result<(bool, bool), Error> {
 }
 
 fn parse_digits(mut n: usize) -> Result<[u8; 6], Error> {
-    if n < 100_000 || n > 999_999 {
+    if !(100_000..=999_999).contains(&n) {
         bail!("Input must be a 6 digit number.")
     }
 
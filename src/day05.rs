@@ -1,32 +1,49 @@
-use crate::computer::{ComputerST, Queue, Rom};
+use crate::computer::{ComputerST, Queue};
 use crate::error::Error;
+use crate::rom::Rom;
+
+pub type Input = Rom;
 
 pub fn run<R>(input: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    let rom = Rom::from_reader(input)?;
-    let mut computer = ComputerST::new(&rom);
+    let rom = parse(input)?;
+
+    Ok((part_one(&rom)?, part_two(&rom)?))
+}
 
-    // Part 1
+pub fn parse<R>(input: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Rom::from_reader(input)
+}
+
+pub fn part_one(rom: &Input) -> Result<String, Error> {
+    let mut computer = ComputerST::new(rom);
     computer.input_mut().enqueue(1);
     computer.run()?;
-    let answer1 = computer
+    let answer = computer
         .output_mut()
         .pop_back()
         .ok_or_else(|| error!("Nothing in output channel for part 1."))?;
 
-    // Part 2
-    let mut computer = ComputerST::new(&rom);
+    Ok(answer.to_string())
+}
+
+pub fn part_two(rom: &Input) -> Result<String, Error> {
+    let mut computer = ComputerST::new(rom);
     computer.input_mut().enqueue(5);
     computer.run()?;
-    let answer2 = computer
+    let answer = computer
         .output_mut()
         .pop_back()
         .ok_or_else(|| error!("Nothing in output channel for part 2."))?;
 
-    Ok((answer1.to_string(), answer2.to_string()))
+    Ok(answer.to_string())
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
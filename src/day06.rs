@@ -4,22 +4,62 @@ use crate::error::Error;
 
 type Ids = HashMap<String, usize>;
 
+pub type Input = Orbits;
+
+pub struct Orbits {
+    directed: Graph<usize>,
+    undirected: Graph<usize>,
+    ids: Ids,
+}
+
 pub fn run<R>(input: R) -> Result<(String, String), Error>
+where
+    R: std::io::BufRead,
+{
+    let orbits = parse(input)?;
+
+    Ok((part_one(&orbits)?, part_two(&orbits)?))
+}
+
+pub fn parse<R>(input: R) -> Result<Input, Error>
 where
     R: std::io::BufRead,
 {
     let (directed, undirected, ids) = parse_input(input)?;
-    let id_com = ids.get("COM").ok_or_else(|| error!("COM node missing"))?;
-    let answer1 = directed.nconnections(id_com);
 
-    let id_you = ids.get("YOU").ok_or_else(|| error!("YOU node missing"))?;
-    let id_san = ids.get("SAN").ok_or_else(|| error!("SAN node missing"))?;
-    let shortest_distance = undirected
+    Ok(Orbits {
+        directed,
+        undirected,
+        ids,
+    })
+}
+
+pub fn part_one(orbits: &Input) -> Result<String, Error> {
+    let id_com = orbits
+        .ids
+        .get("COM")
+        .ok_or_else(|| error!("COM node missing"))?;
+    let answer = orbits.directed.nconnections(id_com);
+
+    Ok(answer.to_string())
+}
+
+pub fn part_two(orbits: &Input) -> Result<String, Error> {
+    let id_you = orbits
+        .ids
+        .get("YOU")
+        .ok_or_else(|| error!("YOU node missing"))?;
+    let id_san = orbits
+        .ids
+        .get("SAN")
+        .ok_or_else(|| error!("SAN node missing"))?;
+    let shortest_distance = orbits
+        .undirected
         .shortest_distance(id_you, id_san)
         .ok_or_else(|| error!("Could not find a path from us to Santa :("))?;
-    let answer2 = shortest_distance - 2;
+    let answer = shortest_distance - 2;
 
-    Ok((answer1.to_string(), answer2.to_string()))
+    Ok(answer.to_string())
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
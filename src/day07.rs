@@ -1,8 +1,9 @@
 use crossbeam::channel;
 use itertools::Itertools;
 
-use crate::computer::{Channel, ComputerMT, Queue, Rom};
+use crate::computer::{Channel, ComputerMT, Queue};
 use crate::error::Error;
+use crate::rom::Rom;
 use crate::utils::math;
 
 // Representation of amplification to Intcode computer for Day 7:
@@ -11,17 +12,41 @@ use crate::utils::math;
 // ...------- com0 ------- com1 -------- com2 ------- com3 ------- com4 -------...
 //
 
+pub type Input = Rom;
+
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
+    let rom = parse(reader)?;
+
+    Ok((part_one(&rom)?, part_two(&rom)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Rom::from_reader(reader)
+}
+
+pub fn part_one(rom: &Input) -> Result<String, Error> {
+    Ok(max_signal(rom, 0..5)?.to_string())
+}
+
+pub fn part_two(rom: &Input) -> Result<String, Error> {
+    Ok(max_signal(rom, 5..10)?.to_string())
+}
+
+/// Returns the largest signal the amplifiers can send to the thrusters using
+/// each phase setting in `phases` exactly once.
+fn max_signal(rom: &Rom, phases: std::ops::Range<i64>) -> Result<i64, Error> {
     let ncomputers = 5;
     let nchannels = math::fact(ncomputers)?;
 
     let barrier = std::sync::Barrier::new(ncomputers);
-    let rom = Rom::from_reader(reader)?;
 
-    let (answer1, answer2) = crossbeam::thread::scope(|s| {
+    crossbeam::thread::scope(|s| {
         let (tx_output, rx_output) = channel::bounded(nchannels);
 
         let mut handles = Vec::new();
@@ -32,11 +57,10 @@ where
             senders.push(tx_input);
 
             let barrier = &barrier;
-            let rom = &rom;
             let tx_output = tx_output.clone();
 
             let handle = s.spawn(move |_| {
-                while let Ok((part, phase_setting, input, output)) = rx_input.recv() {
+                while let Ok((phase_setting, input, output)) = rx_input.recv() {
                     let mut computer = ComputerMT::new(rom, input, output);
 
                     computer.input_mut().enqueue(phase_setting);
@@ -50,7 +74,7 @@ where
 
                     if i == 4 {
                         let answer = computer.output_mut().dequeue()?;
-                        tx_output.send((part, answer)).unwrap();
+                        tx_output.send(answer).unwrap();
                     }
                 }
 
@@ -59,57 +83,34 @@ where
             handles.push(handle);
         }
 
-        for (part, range) in (&[(0..5), (5..10)]).iter().cloned().enumerate() {
-            for phase_settings in range.map(|i| i as i64).permutations(ncomputers) {
-                let channels = (0..ncomputers)
-                    .map(|_| Channel::default())
-                    .collect::<Vec<_>>();
-
-                let mut outputs = (0..ncomputers).map(|i| channels[i].clone());
-                let mut inputs =
-                    (0..ncomputers).map(|i| channels[(i + ncomputers - 1) % ncomputers].clone());
-
-                for i in 0..ncomputers {
-                    let output = outputs.next().unwrap();
-                    let input = inputs.next().unwrap();
-                    senders[i]
-                        .send((part, phase_settings[i], input, output))
-                        .unwrap();
-                }
+        for phase_settings in phases.permutations(ncomputers) {
+            let channels = (0..ncomputers)
+                .map(|_| Channel::default())
+                .collect::<Vec<_>>();
+
+            let mut outputs = (0..ncomputers).map(|i| channels[i].clone());
+            let mut inputs =
+                (0..ncomputers).map(|i| channels[(i + ncomputers - 1) % ncomputers].clone());
+
+            for i in 0..ncomputers {
+                let output = outputs.next().unwrap();
+                let input = inputs.next().unwrap();
+                senders[i].send((phase_settings[i], input, output)).unwrap();
             }
         }
 
         drop(senders);
         drop(tx_output);
 
-        let (mut answer1, mut answer2) = (0, 0);
-
-        let iter = rx_output.iter();
-        for (part, output) in iter {
-            match part {
-                0 => {
-                    if output > answer1 {
-                        answer1 = output;
-                    }
-                }
-                1 => {
-                    if output > answer2 {
-                        answer2 = output;
-                    }
-                }
-                _ => unreachable!(),
-            }
-        }
+        let answer = rx_output.iter().max().unwrap_or(0);
 
         for handle in handles {
             handle.join().unwrap()?;
         }
 
-        Ok::<_, Error>((answer1, answer2))
+        Ok::<_, Error>(answer)
     })
-    .unwrap()?;
-
-    Ok((answer1.to_string(), answer2.to_string()))
+    .unwrap()
 }
 
 #[cfg(test)]
@@ -3,20 +3,33 @@ use crate::error::Error;
 const ROWS: usize = 6;
 const COLS: usize = 25;
 
-pub fn run<R>(mut reader: R) -> Result<(String, String), Error>
+pub type Input = Vec<u8>;
+
+pub fn run<R>(reader: R) -> Result<(String, String), Error>
+where
+    R: std::io::BufRead,
+{
+    let buf = parse(reader)?;
+
+    Ok((part_one(&buf)?, part_two(&buf)?))
+}
+
+pub fn parse<R>(mut reader: R) -> Result<Input, Error>
 where
     R: std::io::BufRead,
 {
-    // Parse input
     let mut buf = Vec::new();
     reader.read_to_end(&mut buf)?;
     buf.pop();
     buf.iter_mut().for_each(|b| *b -= 48);
 
-    // Part 1
-    let answer1 = match buf
+    Ok(buf)
+}
+
+pub fn part_one(buf: &Input) -> Result<String, Error> {
+    let answer = match buf
         .chunks(ROWS * COLS)
-        .fold((std::usize::MAX, None), |mut state, layer| {
+        .fold((usize::MAX, None), |mut state, layer| {
             let num_zeros = bytecount::count(layer, 0);
             if num_zeros < state.0 {
                 state = (num_zeros, Some(layer));
@@ -31,7 +44,10 @@ where
         (_, None) => bail!("Error"),
     };
 
-    // Part 2
+    Ok(answer.to_string())
+}
+
+pub fn part_two(buf: &Input) -> Result<String, Error> {
     let image = buf
         .chunks(ROWS * COLS)
         .fold([2u8; ROWS * COLS], |mut state, layer| {
@@ -44,12 +60,12 @@ where
         });
 
     let mut iter = image.iter();
-    let mut answer2 = String::new();
+    let mut answer = String::new();
     for _ in 0..ROWS {
         for _ in 0..COLS {
             match iter.next() {
-                Some(0) => answer2.push('\u{2585}'),
-                Some(1) => answer2.push(' '),
+                Some(0) => answer.push('\u{2585}'),
+                Some(1) => answer.push(' '),
                 Some(_) => bail!("Bad input: Found digit that is neither 0 nor 1"),
                 None => bail!(
                     "Bad input. Must contain {} rows and {} columns.",
@@ -58,10 +74,10 @@ where
                 ),
             }
         }
-        answer2.push('\n');
+        answer.push('\n');
     }
 
-    Ok((answer1.to_string(), answer2))
+    Ok(answer)
 }
 
 #[cfg(test)]
@@ -1,25 +1,41 @@
-use crate::computer::{ComputerST,Queue, Rom};
+use crate::computer::{ComputerST, Queue};
 use crate::error::Error;
+use crate::rom::Rom;
+
+pub type Input = Rom;
 
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    let rom = Rom::from_reader(reader)?;
-    let mut computer = ComputerST::new(&rom);
+    let rom = parse(reader)?;
 
-    computer.input_mut().enqueue(1);
-    computer.run()?;
-    let answer1 = computer.output_mut().dequeue()?;
+    Ok((part_one(&rom)?, part_two(&rom)?))
+}
 
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Rom::from_reader(reader)
+}
+
+pub fn part_one(rom: &Input) -> Result<String, Error> {
+    boost(rom, 1)
+}
+
+pub fn part_two(rom: &Input) -> Result<String, Error> {
+    boost(rom, 2)
+}
 
-    let mut computer = ComputerST::new(&rom);
+fn boost(rom: &Rom, mode: i64) -> Result<String, Error> {
+    let mut computer = ComputerST::new(rom);
 
-    computer.input_mut().enqueue(2);
+    computer.input_mut().enqueue(mode);
     computer.run()?;
-    let answer2 = computer.output_mut().dequeue()?;
+    let answer = computer.output_mut().dequeue()?;
 
-    Ok((answer1.to_string(), answer2.to_string()))
+    Ok(answer.to_string())
 }
 
 #[cfg(test)]
@@ -31,18 +31,18 @@ impl Asteroid {
 
 impl PartialOrd for Asteroid {
     fn partial_cmp(&self, other: &Asteroid) -> Option<Ordering> {
-        match self.angle.partial_cmp(&other.angle) {
-            Some(Ordering::Less) => Some(Ordering::Less),
-            Some(Ordering::Greater) => Some(Ordering::Greater),
-            Some(Ordering::Equal) => Some(self.distance_squared.cmp(&other.distance_squared)),
-            None => unreachable!(),
-        }
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for Asteroid {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        match self.angle.partial_cmp(&other.angle) {
+            Some(Ordering::Less) => Ordering::Less,
+            Some(Ordering::Greater) => Ordering::Greater,
+            Some(Ordering::Equal) => self.distance_squared.cmp(&other.distance_squared),
+            None => unreachable!(),
+        }
     }
 }
 
@@ -99,35 +99,49 @@ fn polar_coordinates_transformation(angle: f64) -> f64 {
     }
 }
 
+pub type Input = Field;
+
+/// The asteroids, along with the best location for a monitoring station.
+pub struct Field {
+    points: Vec<Point>,
+    station: Point,
+    /// Number of asteroids visible from the station.
+    visible: usize,
+}
+
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    let points = parse_input(reader)?;
+    let field = parse(reader)?;
+
+    Ok((part_one(&field)?, part_two(&field)?))
+}
 
-    // Part 1
-    let (answer1, laser) = part1(&points)?;
+pub fn part_one(field: &Input) -> Result<String, Error> {
+    Ok(field.visible.to_string())
+}
 
-    // Part 2
-    let mut asteroids = part2(laser, &points)?;
+pub fn part_two(field: &Input) -> Result<String, Error> {
+    let mut asteroids = vaporize(field.station, &field.points)?;
 
     let asteroid = asteroids
         .nth(199)
         .ok_or_else(|| error!("Could not find 200th asteroid."))?;
-    let answer2 = asteroid.point.x() * 100 + asteroid.point.y();
+    let answer = asteroid.point.x() * 100 + asteroid.point.y();
 
-    Ok((answer1.to_string(), answer2.to_string()))
+    Ok(answer.to_string())
 }
 
-fn part1(points: &[Point]) -> Result<(usize, Point), Error> {
+/// Returns the number of asteroids visible from the best monitoring station,
+/// along with its location.
+fn station(points: &[Point]) -> Result<(usize, Point), Error> {
     let mut map: HashMap<Point, HashSet<Direction>> = HashMap::new();
 
     for origin in points {
         for other in points {
             let direction = Direction::new(*origin, *other)?;
-            map.entry(*origin)
-                .or_insert_with(HashSet::new)
-                .insert(direction);
+            map.entry(*origin).or_default().insert(direction);
         }
     }
 
@@ -139,12 +153,12 @@ fn part1(points: &[Point]) -> Result<(usize, Point), Error> {
         state
     });
 
-    let point = *point.ok_or_else(|| error!("Could not find 200th asteroid."))?;
+    let point = *point.ok_or_else(|| error!("Could not find a monitoring station."))?;
 
     Ok((max, point))
 }
 
-fn part2(laser: Point, points: &[Point]) -> Result<Asteroids, Error> {
+fn vaporize(laser: Point, points: &[Point]) -> Result<Asteroids, Error> {
     let vec = points
         .iter()
         .filter(|point| **point != laser)
@@ -154,7 +168,7 @@ fn part2(laser: Point, points: &[Point]) -> Result<Asteroids, Error> {
     Ok(Asteroids::new(vec))
 }
 
-fn parse_input<R>(mut reader: R) -> Result<Vec<Point>, Error>
+pub fn parse<R>(mut reader: R) -> Result<Input, Error>
 where
     R: std::io::BufRead,
 {
@@ -178,7 +192,13 @@ where
         buf.clear();
     }
 
-    Ok(points)
+    let (visible, station) = station(&points)?;
+
+    Ok(Field {
+        points,
+        station,
+        visible,
+    })
 }
 
 #[cfg(test)]
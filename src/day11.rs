@@ -5,26 +5,42 @@ use std::fmt;
 use crossbeam::channel::{Receiver, Sender};
 use crossbeam::thread;
 
-use crate::computer::{Channel, ComputerMT, Rom};
+use crate::computer::{Channel, ComputerMT};
 use crate::error::Error;
+use crate::rom::Rom;
 use crate::utils::Vec2;
 
 type Point = Vec2<i64>;
 
+pub type Input = Rom;
+
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    // Part 1
-    let rom = Rom::from_reader(reader)?;
-    let robot = Robot::run(&rom, Color::Black)?;
-    let answer1 = robot.grid.keys().count();
+    let rom = parse(reader)?;
+
+    Ok((part_one(&rom)?, part_two(&rom)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Rom::from_reader(reader)
+}
+
+pub fn part_one(rom: &Input) -> Result<String, Error> {
+    let robot = Robot::run(rom, Color::Black)?;
+    let answer = robot.grid.keys().count();
+
+    Ok(answer.to_string())
+}
 
-    // Part 2
-    let robot = Robot::run(&rom, Color::White)?;
-    let answer2 = robot.to_string();
+pub fn part_two(rom: &Input) -> Result<String, Error> {
+    let robot = Robot::run(rom, Color::White)?;
 
-    Ok((answer1.to_string(), answer2))
+    Ok(robot.to_string())
 }
 
 struct Robot {
@@ -5,74 +5,112 @@ use std::ops::{Deref, DerefMut};
 use crate::error::Error;
 use crate::utils::{math::lcm, Vec3};
 
-#[cfg(not(all(
-    any(target_arch = "x86", target_arch = "x86_64"),
-    target_feature = "avx2"
-)))]
-use self::normal::Moon;
-
-#[cfg(all(
-    any(target_arch = "x86", target_arch = "x86_64"),
-    target_feature = "avx2"
-))]
-use self::simd::Moon;
-
 const PAIRS: [(usize, usize); 6] = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
 
+/// Initial positions of the four moons.
+pub type Input = [[i64; 3]; 4];
+
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    let mut moons = parse_input(reader)?;
+    let positions = parse(reader)?;
 
-    let mut num_steps = 0;
-    let mut answer1 = Err(error!("Did not complete 1_000 steps."));
+    Ok((part_one(&positions)?, part_two(&positions)?))
+}
 
-    let mut seen = [HashSet::new(), HashSet::new(), HashSet::new()];
-    let mut counts = [None, None, None];
+pub fn part_one(positions: &Input) -> Result<String, Error> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if let Some(avx2) = simd::Avx2::detect() {
+            return Ok(simd::energy(positions, 1_000, avx2).to_string());
+        }
+    }
 
-    loop {
-        let state = moons.state();
+    Ok(energy::<normal::Moon>(positions, 1_000, ()).to_string())
+}
 
-        for coord in 0..3 {
-            if counts[coord].is_none() && !seen[coord].insert(state[coord]) {
-                counts[coord] = Some(num_steps)
-            }
+pub fn part_two(positions: &Input) -> Result<String, Error> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if let Some(avx2) = simd::Avx2::detect() {
+            return Ok(simd::period(positions, avx2)?.to_string());
         }
+    }
 
-        let finished = counts.iter().all(|count| count.is_some());
+    Ok(period::<normal::Moon>(positions, ())?.to_string())
+}
 
-        if finished {
-            break;
-        }
+/// Returns the total energy of the system after `steps` steps.
+fn energy<M>(positions: &Input, steps: usize, token: M::Token) -> u64
+where
+    M: Body,
+{
+    let mut moons = Moons::<M>::new(positions, token);
+
+    for _ in 0..steps {
+        M::step(&mut moons);
+    }
 
-        moons.step();
-        num_steps += 1;
+    moons.energy()
+}
 
-        if num_steps == 1_000 {
-            answer1 = Ok(moons.energy());
-        }
+/// Returns the number of steps until the system first returns to a previous
+/// state.
+fn period<M>(positions: &Input, token: M::Token) -> Result<u64, Error>
+where
+    M: Body,
+{
+    let mut moons = Moons::<M>::new(positions, token);
+    let mut cycles = Cycles::default();
+
+    while !cycles.record(moons.state()) {
+        M::step(&mut moons);
     }
 
-    let answer2 = lcm(
-        lcm(counts[0].unwrap(), counts[1].unwrap())?,
-        counts[2].unwrap(),
-    )?;
+    cycles.period()
+}
+
+/// The states seen along each axis. Each axis is independent, so the period
+/// of the whole system is the lcm of the per-axis cycles.
+#[derive(Debug, Default)]
+struct Cycles {
+    seen: [HashSet<[(i64, i64); 4]>; 3],
+    counts: [Option<u64>; 3],
+    num_steps: u64,
+}
+
+impl Cycles {
+    /// Records `state` as the state after `num_steps` steps, noting each axis
+    /// the first time it repeats, and returns whether every axis has now
+    /// repeated.
+    fn record(&mut self, state: [[(i64, i64); 4]; 3]) -> bool {
+        let axes = state.iter().zip(&mut self.seen).zip(&mut self.counts);
+        for ((state, seen), count) in axes {
+            if count.is_none() && !seen.insert(*state) {
+                *count = Some(self.num_steps)
+            }
+        }
+        self.num_steps += 1;
+
+        self.counts.iter().all(|count| count.is_some())
+    }
 
-    Ok((answer1?.to_string(), answer2.to_string()))
+    fn period(&self) -> Result<u64, Error> {
+        match self.counts {
+            [Some(x), Some(y), Some(z)] => lcm(lcm(x, y)?, z),
+            _ => bail!("Not every axis has repeated yet."),
+        }
+    }
 }
 
-fn parse_input<R>(reader: R) -> Result<Moons, Error>
+pub fn parse<R>(reader: R) -> Result<Input, Error>
 where
     R: std::io::BufRead,
 {
-    // safety: This is safe because the code below ensures that by the time we
-    // would ever try to touch the moons array, all values inside will contain
-    // specific values that we have written to it.
-    let mut moons: [RefCell<Moon>; 4] = unsafe { std::mem::MaybeUninit::uninit().assume_init() };
-    let mut count = 0;
+    let mut positions = Vec::with_capacity(4);
     for res in reader.lines() {
-        if count > 3 {
+        if positions.len() > 3 {
             bail!("Can only support exactly 4 moons.");
         }
 
@@ -100,33 +138,63 @@ where
             bail!("Found {} coordinate(s), but need 3.", count_coord);
         }
 
-        let moon = Moon::new(pos, Vec3::default());
-        moons[count] = RefCell::new(moon);
-        count += 1;
+        positions.push(pos);
     }
 
-    if count != 4 {
+    if positions.len() != 4 {
         bail!("Can only suport exactly 4 moons.");
     }
 
-    Ok(Moons(moons))
+    Ok([positions[0], positions[1], positions[2], positions[3]])
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-struct Moons([RefCell<Moon>; 4]);
+/// A single moon's position and velocity.
+///
+/// `normal::Moon` works everywhere, while `simd::Moon` keeps each vector in an
+/// AVX2 register, so creating one takes a `Token` proving AVX2 support.
+trait Body: Copy {
+    type Token: Copy;
+
+    /// A moon at `pos`, at rest.
+    fn new(pos: [i64; 3], token: Self::Token) -> Self;
+
+    fn pos(&self) -> Vec3<i64>;
+
+    fn vel(&self) -> Vec3<i64>;
+
+    /// Advances every moon by one time step.
+    fn step(moons: &mut Moons<Self>);
+
+    fn state(&self) -> [(i64, i64); 3] {
+        let (pos, vel) = (self.pos(), self.vel());
+        [(pos.x(), vel.x()), (pos.y(), vel.y()), (pos.z(), vel.z())]
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Moons<M>([RefCell<M>; 4]);
+
+impl<M> Moons<M>
+where
+    M: Body,
+{
+    fn new(positions: &Input, token: M::Token) -> Self {
+        let moon = |i: usize| RefCell::new(M::new(positions[i], token));
+        Moons([moon(0), moon(1), moon(2), moon(3)])
+    }
 
-impl Moons {
     fn energy(&self) -> u64 {
         let mut total = 0;
 
         for moon in self.iter() {
             let moon = moon.borrow();
+            let (pos, vel) = (moon.pos(), moon.vel());
             let mut potential = 0;
             let mut kinetic = 0;
 
             for k in 0..3 {
-                potential += moon.pos()[k].abs() as u64;
-                kinetic += moon.vel()[k].abs() as u64;
+                potential += pos[k].unsigned_abs();
+                kinetic += vel[k].unsigned_abs();
             }
 
             total += potential * kinetic;
@@ -135,15 +203,14 @@ impl Moons {
         total
     }
 
-    pub(crate) fn state(&self) -> [[(i64, i64); 4]; 3] {
-        // safety: code below ensures we're filling uninitialized array with actual values
-        let mut a: [[(i64, i64); 4]; 3] = unsafe { std::mem::MaybeUninit::uninit().assume_init() };
+    fn state(&self) -> [[(i64, i64); 4]; 3] {
+        let mut a = [[(0i64, 0i64); 4]; 3];
 
-        for moon in 0..4 {
-            let state = self.0[moon].borrow().state();
+        for (i, moon) in self.0.iter().enumerate() {
+            let state = moon.borrow().state();
 
-            for coord in 0..3 {
-                a[coord][moon] = state[coord];
+            for (coord, s) in state.iter().enumerate() {
+                a[coord][i] = *s;
             }
         }
 
@@ -151,209 +218,193 @@ impl Moons {
     }
 }
 
-impl Deref for Moons {
-    type Target = [RefCell<Moon>];
+impl<M> Deref for Moons<M> {
+    type Target = [RefCell<M>];
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl DerefMut for Moons {
+impl<M> DerefMut for Moons<M> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
 }
 
-#[cfg(not(all(
-    any(target_arch = "x86", target_arch = "x86_64"),
-    target_feature = "avx2"
-)))]
 mod normal {
     use super::*;
 
-    impl Moons {
-        pub(crate) fn step(&mut self) {
+    #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+    pub(super) struct Moon {
+        pos: Vec3<i64>,
+        vel: Vec3<i64>,
+    }
+
+    impl Body for Moon {
+        type Token = ();
+
+        fn new(pos: [i64; 3], _: ()) -> Self {
+            Self {
+                pos: pos.into(),
+                vel: Vec3::default(),
+            }
+        }
+
+        fn pos(&self) -> Vec3<i64> {
+            self.pos
+        }
+
+        fn vel(&self) -> Vec3<i64> {
+            self.vel
+        }
+
+        fn step(moons: &mut Moons<Self>) {
             for (i, j) in PAIRS.iter() {
-                let moon_i = self.0.get(*i).unwrap();
-                let moon_j = self.0.get(*j).unwrap();
+                let mut moon_i = moons[*i].borrow_mut();
+                let mut moon_j = moons[*j].borrow_mut();
 
                 for k in 0..3 {
-                    let pos_i = moon_i.borrow().pos()[k];
-                    let pos_j = moon_j.borrow().pos()[k];
+                    let pos_i = moon_i.pos[k];
+                    let pos_j = moon_j.pos[k];
 
                     if pos_i < pos_j {
-                        moon_i.borrow_mut().vel_mut()[k] += 1;
-                        moon_j.borrow_mut().vel_mut()[k] -= 1;
+                        moon_i.vel[k] += 1;
+                        moon_j.vel[k] -= 1;
                     } else if pos_i > pos_j {
-                        moon_i.borrow_mut().vel_mut()[k] -= 1;
-                        moon_j.borrow_mut().vel_mut()[k] += 1;
+                        moon_i.vel[k] -= 1;
+                        moon_j.vel[k] += 1;
                     }
                 }
             }
 
-            for moon in self.iter_mut() {
+            for moon in moons.iter_mut() {
+                let moon = moon.get_mut();
                 for k in 0..3 {
-                    let vel = moon.borrow().vel()[k];
-                    moon.borrow_mut().pos_mut()[k] += vel;
+                    moon.pos[k] += moon.vel[k];
                 }
             }
         }
     }
+}
 
-    #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
-    pub(crate) struct Moon {
-        pub(crate) pos: Vec3<i64>,
-        pub(crate) vel: Vec3<i64>,
-    }
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod simd {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
 
-    impl Moon {
-        pub(crate) fn new<V, U>(pos: V, vel: U) -> Self
-        where
-            V: Into<Vec3<i64>>,
-            U: Into<Vec3<i64>>,
-        {
-            Self {
-                pos: pos.into(),
-                vel: vel.into(),
-            }
-        }
+    use super::*;
 
-        pub(crate) fn pos(&self) -> &Vec3<i64> {
-            &self.pos
+    /// Proof that the CPU supports AVX2, which can only be had by detecting it.
+    #[derive(Copy, Clone, Debug)]
+    pub(super) struct Avx2(());
+
+    impl Avx2 {
+        pub(super) fn detect() -> Option<Self> {
+            if is_x86_feature_detected!("avx2") {
+                Some(Self(()))
+            } else {
+                None
+            }
         }
+    }
 
-        pub(crate) fn pos_mut(&mut self) -> &mut Vec3<i64> {
-            &mut self.pos
-        }
+    /// `super::energy` with the whole loop compiled for AVX2.
+    pub(super) fn energy(positions: &Input, steps: usize, avx2: Avx2) -> u64 {
+        #[target_feature(enable = "avx2")]
+        unsafe fn energy(positions: &Input, steps: usize, avx2: Avx2) -> u64 {
+            let mut moons = Moons::<Moon>::new(positions, avx2);
 
-        pub(crate) fn vel(&self) -> &Vec3<i64> {
-            &self.vel
-        }
+            for _ in 0..steps {
+                step(&mut moons);
+            }
 
-        pub(crate) fn vel_mut(&mut self) -> &mut Vec3<i64> {
-            &mut self.vel
+            moons.energy()
         }
 
-        pub(crate) fn state(&self) -> [(i64, i64); 3] {
-            [
-                (self.pos.x(), self.vel.x()),
-                (self.pos.y(), self.vel.y()),
-                (self.pos.z(), self.vel.z()),
-            ]
-        }
+        // Safety: `avx2` proves AVX2 is supported.
+        unsafe { energy(positions, steps, avx2) }
     }
-}
 
-#[cfg(all(
-    any(target_arch = "x86", target_arch = "x86_64"),
-    target_feature = "avx2"
-))]
-mod simd {
-    #[cfg(target_arch = "x86")]
-    use std::arch::x86::*;
-    #[cfg(target_arch = "x86_64")]
-    use std::arch::x86_64::*;
+    /// `super::period` with the whole loop compiled for AVX2.
+    pub(super) fn period(positions: &Input, avx2: Avx2) -> Result<u64, Error> {
+        #[target_feature(enable = "avx2")]
+        unsafe fn period(positions: &Input, avx2: Avx2) -> Result<u64, Error> {
+            let mut moons = Moons::<Moon>::new(positions, avx2);
+            let mut cycles = Cycles::default();
 
-    use lazy_static::lazy_static;
+            while !cycles.record(moons.state()) {
+                step(&mut moons);
+            }
 
-    use super::*;
+            cycles.period()
+        }
 
-    lazy_static! {
-        static ref ONE: __m256i = unsafe { _mm256_set_epi64x(1, 1, 1, 0) };
-        static ref NEGATIVE_ONE: __m256i = unsafe { _mm256_set_epi64x(-1, -1, -1, 0) };
+        // Safety: `avx2` proves AVX2 is supported.
+        unsafe { period(positions, avx2) }
     }
-    impl Moons {
-        pub(crate) fn step(&mut self) {
-            for (i, j) in PAIRS.iter() {
-                let moon_i = self.0.get(*i).unwrap();
-                let moon_j = self.0.get(*j).unwrap();
 
-                let pos_i = moon_i.borrow().pos;
-                let pos_j = moon_j.borrow().pos;
+    #[target_feature(enable = "avx2")]
+    unsafe fn step(moons: &mut Moons<Moon>) {
+        let one = _mm256_set_epi64x(1, 1, 1, 0);
+        let negative_one = _mm256_set_epi64x(-1, -1, -1, 0);
 
-                // Adding
-                let mask_gt = unsafe { _mm256_cmpgt_epi64(pos_i, pos_j) };
-                let operand_add = unsafe { _mm256_and_si256(mask_gt, *NEGATIVE_ONE) };
+        for (i, j) in PAIRS.iter() {
+            let mut moon_i = moons[*i].borrow_mut();
+            let mut moon_j = moons[*j].borrow_mut();
 
-                // Subtracting
-                let mask_lt = unsafe { _mm256_cmpgt_epi64(pos_j, pos_i) };
-                let operand_sub = unsafe { _mm256_and_si256(mask_lt, *ONE) };
+            // Adding
+            let mask_gt = _mm256_cmpgt_epi64(moon_i.pos, moon_j.pos);
+            let operand_add = _mm256_and_si256(mask_gt, negative_one);
 
-                let operand = unsafe { _mm256_or_si256(operand_add, operand_sub) };
+            // Subtracting
+            let mask_lt = _mm256_cmpgt_epi64(moon_j.pos, moon_i.pos);
+            let operand_sub = _mm256_and_si256(mask_lt, one);
 
-                let mut moon_ref = moon_i.borrow_mut();
-                let vel_ref = moon_ref.vel_mut();
-                *vel_ref = unsafe { _mm256_add_epi64(*vel_ref, operand) };
+            let operand = _mm256_or_si256(operand_add, operand_sub);
 
-                let mut moon_ref = moon_j.borrow_mut();
-                let vel_ref = moon_ref.vel_mut();
-                *vel_ref = unsafe { _mm256_sub_epi64(*vel_ref, operand) };
-            }
+            moon_i.vel = _mm256_add_epi64(moon_i.vel, operand);
+            moon_j.vel = _mm256_sub_epi64(moon_j.vel, operand);
+        }
 
-            for moon in self.iter_mut() {
-                let new_pos = {
-                    let moon = moon.borrow();
-                    unsafe { _mm256_add_epi64(moon.pos, moon.vel) }
-                };
-                let mut moon = moon.borrow_mut();
-                *moon.pos_mut() = new_pos;
-            }
+        for moon in moons.iter_mut() {
+            let moon = moon.get_mut();
+            moon.pos = _mm256_add_epi64(moon.pos, moon.vel);
         }
     }
 
+    /// Can only be created with an `Avx2` token, so every method may assume
+    /// AVX2 support.
     #[derive(Copy, Clone, Debug)]
-    pub(crate) struct Moon {
+    pub(super) struct Moon {
         pos: __m256i,
         vel: __m256i,
     }
 
-    impl PartialEq for Moon {
-        fn eq(&self, other: &Moon) -> bool {
-            self.pos() == other.pos() && self.vel() == other.vel()
-        }
-    }
+    impl Body for Moon {
+        type Token = Avx2;
 
-    impl Eq for Moon {}
-
-    impl Moon {
-        pub(crate) fn new<V, U>(pos: V, vel: U) -> Self
-        where
-            V: Into<Vec3<i64>>,
-            U: Into<Vec3<i64>>,
-        {
-            let pos = {
-                let pos = pos.into();
-                unsafe { _mm256_set_epi64x(pos.x(), pos.y(), pos.z(), 0) }
-            };
-            let vel = {
-                let vel = vel.into();
-                unsafe { _mm256_set_epi64x(vel.x(), vel.y(), vel.z(), 0) }
-            };
+        fn new(pos: [i64; 3], _: Avx2) -> Self {
+            // Safety: the token proves AVX2 is supported.
+            let pos = unsafe { _mm256_set_epi64x(pos[0], pos[1], pos[2], 0) };
+            let vel = unsafe { _mm256_setzero_si256() };
 
             Self { pos, vel }
         }
 
-        pub(crate) fn pos(&self) -> Vec3<i64> {
+        fn pos(&self) -> Vec3<i64> {
             self.pos.into()
         }
 
-        pub(crate) fn pos_mut(&mut self) -> &mut __m256i {
-            &mut self.pos
-        }
-
-        pub(crate) fn vel(&self) -> Vec3<i64> {
+        fn vel(&self) -> Vec3<i64> {
             self.vel.into()
         }
 
-        pub(crate) fn vel_mut(&mut self) -> &mut __m256i {
-            &mut self.vel
-        }
-
-        pub(crate) fn state(&self) -> [(i64, i64); 3] {
-            let pos: Vec3<i64> = self.pos.into();
-            let vel: Vec3<i64> = self.vel.into();
-            [(pos.x(), vel.x()), (pos.y(), vel.y()), (pos.z(), vel.z())]
+        fn step(moons: &mut Moons<Self>) {
+            // Safety: a `Moon` can't exist without AVX2 support.
+            unsafe { step(moons) }
         }
     }
 }
@@ -382,22 +433,47 @@ mod tests {
             ),
         ];
 
-        for (input, steps, expected1, _) in test_cases {
+        for (input, steps, expected1, expected2) in test_cases {
             let reader = std::io::BufReader::new(input.as_bytes());
 
-            let mut moons = parse_input(reader).unwrap();
-
-            for _ in 0..*steps {
-                moons.step()
-            }
+            let positions = parse(reader).unwrap();
 
-            let actual1 = moons.energy();
+            let actual1 = energy::<normal::Moon>(&positions, *steps, ());
 
+            let actual2 = part_two(&positions).unwrap();
 
             assert_eq!(actual1, *expected1);
-            // assert_eq!(actual2, *expected2);
+            assert_eq!(actual2, expected2.to_string());
         }
 
         utils::tests::test_full_problem(12, run, "7928", "518311327635164");
     }
+
+    #[test]
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn test_day12_simd() {
+        let avx2 = match simd::Avx2::detect() {
+            Some(avx2) => avx2,
+            None => return,
+        };
+
+        let inputs = &[
+            "<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>".to_string(),
+            "<x=-8, y=-10, z=0>\n<x=5, y=5, z=10>\n<x=2, y=-7, z=3>\n<x=9, y=-8, z=-3>".to_string(),
+            std::fs::read_to_string("input/day12.txt").unwrap(),
+        ];
+
+        for input in inputs {
+            let positions = parse(input.as_bytes()).unwrap();
+
+            for steps in &[0, 1, 10, 100, 1_000] {
+                let expected = energy::<normal::Moon>(&positions, *steps, ());
+                assert_eq!(simd::energy(&positions, *steps, avx2), expected);
+                assert_eq!(energy::<simd::Moon>(&positions, *steps, avx2), expected);
+            }
+
+            let expected = period::<normal::Moon>(&positions, ()).unwrap();
+            assert_eq!(simd::period(&positions, avx2).unwrap(), expected);
+        }
+    }
 }
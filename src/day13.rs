@@ -1,19 +1,47 @@
-use crate::computer::{ComputerST, Queue, Rom, State};
+use crate::computer::{ComputerST, Queue, State};
 use crate::error::Error;
+use crate::patch::Patch;
+use crate::rom::Rom;
 
 const ROWS: usize = 26;
 const COLS: usize = 40;
 
+pub type Input = Rom;
+
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    let rom = Rom::from_reader(reader)?;
-    let mut game = Game::new(&rom);
+    let rom = parse(reader)?;
+
+    Ok((part_one(&rom)?, part_two(&rom)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Rom::from_reader(reader)
+}
 
+pub fn part_one(rom: &Input) -> Result<String, Error> {
+    // The blocks are counted the first time the game asks for input.
+    let mut game = Game::new(rom)?;
+    game.step()?;
+
+    Ok(game.num_blocks.to_string())
+}
+
+pub fn part_two(rom: &Input) -> Result<String, Error> {
+    let mut game = Game::new(rom)?;
     game.run()?;
 
-    Ok((game.num_blocks.to_string(), game.score.to_string()))
+    Ok(game.score.to_string())
+}
+
+/// Sets address 0 to 2 so the game can be played without quarters.
+pub fn free_play() -> Patch {
+    Patch::new(vec![(0, 2)])
 }
 
 pub struct Game {
@@ -34,14 +62,14 @@ pub struct Game {
 }
 
 impl Game {
-    pub fn new<R>(rom: R) -> Self
+    pub fn new<R>(rom: R) -> Result<Self, Error>
     where
         R: AsRef<[i64]>,
     {
         let mut computer = ComputerST::new(rom.as_ref());
-        computer.write(0, 2);
+        computer.patch(&free_play())?;
 
-        Self {
+        Ok(Self {
             computer,
             display: vec![0; COLS * ROWS],
 
@@ -56,15 +84,12 @@ impl Game {
 
             num_blocks: 0,
             first: true,
-        }
+        })
     }
 
     pub fn run(&mut self) -> Result<(), Error> {
-        loop {
-            match self.step()? {
-                Some(next_move) => self.computer.input_mut().enqueue(next_move),
-                None => break,
-            }
+        while let Some(next_move) = self.step()? {
+            self.computer.input_mut().enqueue(next_move);
         }
 
         Ok(())
@@ -81,11 +106,11 @@ impl Game {
                     }
 
                     if self.paddle < self.ball {
-                        return Ok(Some(1))
+                        return Ok(Some(1));
                     } else if self.paddle > self.ball {
-                        return Ok(Some(-1))
+                        return Ok(Some(-1));
                     } else {
-                        return Ok(Some(0))
+                        return Ok(Some(0));
                     }
                 }
                 State::HasOutput => {
@@ -114,7 +139,7 @@ impl Game {
                         }
 
                         match id {
-                            0 | 1 | 2 => (),
+                            0..=2 => (),
                             3 => self.paddle = x,
                             4 => self.ball = x,
                             _ => bail!("Received invalid id: {}", id),
@@ -1,15 +1,395 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
 use crate::error::Error;
 
-pub fn run<R>(reader: R) -> Result<(String, String), Error>
+const ORE: &str = "ORE";
+const FUEL: &str = "FUEL";
+const CARGO_ORE: u64 = 1_000_000_000_000;
+
+pub type Input = Reactions;
+
+pub fn run<R>(input: R) -> Result<(String, String), Error>
+where
+    R: std::io::BufRead,
+{
+    let reactions = parse(input)?;
+
+    Ok((part_one(&reactions)?, part_two(&reactions)?))
+}
+
+pub fn parse<R>(input: R) -> Result<Input, Error>
 where
     R: std::io::BufRead,
 {
-    Ok((String::new(), String::new()))
+    let mut builder = Builder::default();
+
+    for (i, res) in input.lines().enumerate() {
+        let line = res?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        builder
+            .add(line)
+            .map_err(|e| error!("{} (line {})", e, i + 1))?;
+    }
+
+    builder.build()
+}
+
+pub fn part_one(reactions: &Input) -> Result<String, Error> {
+    Ok(reactions.ore_for(FUEL, 1)?.to_string())
+}
+
+pub fn part_two(reactions: &Input) -> Result<String, Error> {
+    Ok(reactions.max_produced(FUEL, CARGO_ORE)?.to_string())
+}
+
+/// A nanofactory's list of reactions, each producing a single chemical from
+/// some quantity of others, ultimately starting from `ORE`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reactions {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    /// The reaction producing each chemical, indexed by id. `ORE` has none.
+    reactions: Vec<Option<Reaction>>,
+    /// Every chemical, ordered so that each comes before all of its inputs.
+    order: Vec<usize>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Reaction {
+    quantity: u64,
+    inputs: Vec<(usize, u64)>,
+}
+
+/// The result of producing a chemical: the `ORE` consumed and whatever was
+/// left over from reactions that made more than was needed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Production {
+    pub ore: u64,
+    pub leftovers: HashMap<String, u64>,
+}
+
+impl Reactions {
+    /// Returns the `ORE` needed to make `quantity` of `chemical`.
+    pub fn ore_for(&self, chemical: &str, quantity: u64) -> Result<u64, Error> {
+        Ok(self.produce(chemical, quantity)?.ore)
+    }
+
+    /// Works out how to make `quantity` of `chemical`, running each reaction
+    /// only as often as needed.
+    pub fn produce(&self, chemical: &str, quantity: u64) -> Result<Production, Error> {
+        let id = self.id(chemical)?;
+
+        let mut needed = vec![0u64; self.names.len()];
+        let mut leftovers = HashMap::new();
+        needed[id] = quantity;
+
+        let too_much = |id: usize| {
+            error!(
+                "Too much {} needed to make {} {}.",
+                self.names[id], quantity, chemical
+            )
+        };
+
+        // Everything that consumes a chemical comes before it in `order`, so
+        // by the time we reach it, the total needed is known.
+        for &id in &self.order {
+            let reaction = match &self.reactions[id] {
+                Some(reaction) => reaction,
+                None => continue,
+            };
+            if needed[id] == 0 {
+                continue;
+            }
+
+            let times = needed[id].div_ceil(reaction.quantity);
+            let made = times
+                .checked_mul(reaction.quantity)
+                .ok_or_else(|| too_much(id))?;
+            if made > needed[id] {
+                leftovers.insert(self.names[id].clone(), made - needed[id]);
+            }
+
+            for &(input, amount) in &reaction.inputs {
+                needed[input] = times
+                    .checked_mul(amount)
+                    .and_then(|n| n.checked_add(needed[input]))
+                    .ok_or_else(|| too_much(input))?;
+            }
+        }
+
+        let ore = match self.ids.get(ORE) {
+            Some(&ore) => needed[ore],
+            None => 0,
+        };
+
+        Ok(Production { ore, leftovers })
+    }
+
+    /// Returns the most of `chemical` that can be made from `ore` `ORE`.
+    pub fn max_produced(&self, chemical: &str, ore: u64) -> Result<u64, Error> {
+        let per_unit = self.ore_for(chemical, 1)?;
+        if per_unit == 0 {
+            bail!(
+                "{} does not need any {}, so there is no limit.",
+                chemical,
+                ORE
+            );
+        }
+
+        // Leftovers from earlier units only make later units cheaper, so
+        // `ore / per_unit` can always be made. Double from there to find an
+        // upper bound, then binary search between the two.
+        let mut lo = ore / per_unit;
+        let mut hi = std::cmp::max(lo, 1);
+        while self.ore_for(chemical, hi)? <= ore {
+            lo = hi;
+            hi = hi
+                .checked_mul(2)
+                .ok_or_else(|| error!("Too much {} to search.", chemical))?;
+        }
+
+        // Invariant: `lo` can be made and `hi` can't.
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if self.ore_for(chemical, mid)? <= ore {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        Ok(lo)
+    }
+
+    /// Iterates over every chemical, each before all of its inputs.
+    pub fn chemicals(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().map(move |&id| self.names[id].as_str())
+    }
+
+    fn id(&self, chemical: &str) -> Result<usize, Error> {
+        self.ids
+            .get(chemical)
+            .copied()
+            .ok_or_else(|| error!("Unknown chemical {:?}.", chemical))
+    }
+}
+
+impl FromStr for Reactions {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s.as_bytes())
+    }
+}
+
+impl fmt::Display for Reactions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &id in &self.order {
+            let reaction = match &self.reactions[id] {
+                Some(reaction) => reaction,
+                None => continue,
+            };
+            for (i, (input, amount)) in reaction.inputs.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{} {}", amount, self.names[*input])?;
+            }
+            writeln!(f, " => {} {}", reaction.quantity, self.names[id])?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Builder {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    reactions: Vec<Option<Reaction>>,
+}
+
+impl Builder {
+    /// Adds a reaction such as `7 A, 1 B => 1 C`.
+    fn add(&mut self, line: &str) -> Result<(), Error> {
+        let mut sides = line.splitn(2, "=>");
+        let (lhs, rhs) = match (sides.next(), sides.next()) {
+            (Some(lhs), Some(rhs)) => (lhs, rhs),
+            _ => bail!("Expected `inputs => output`, but got {:?}.", line),
+        };
+
+        let (quantity, output) = self.term(rhs)?;
+        if self.names[output] == ORE {
+            bail!("{} cannot be produced by a reaction.", ORE);
+        }
+        if self.reactions[output].is_some() {
+            bail!(
+                "{} is produced by more than one reaction.",
+                self.names[output]
+            );
+        }
+
+        let mut inputs = Vec::new();
+        for term in lhs.split(',') {
+            let (amount, input) = self.term(term)?;
+            if input == output {
+                bail!("{} cannot be an input to itself.", self.names[output]);
+            }
+            inputs.push((input, amount));
+        }
+
+        self.reactions[output] = Some(Reaction { quantity, inputs });
+
+        Ok(())
+    }
+
+    /// Parses a term such as `7 A`, returning the quantity and chemical id.
+    fn term(&mut self, s: &str) -> Result<(u64, usize), Error> {
+        let mut words = s.split_whitespace();
+        let (quantity, name) = match (words.next(), words.next(), words.next()) {
+            (Some(quantity), Some(name), None) => (quantity, name),
+            _ => bail!("Expected `quantity chemical`, but got {:?}.", s.trim()),
+        };
+        let quantity = quantity.parse::<u64>()?;
+        if quantity == 0 {
+            bail!("Quantity of {} must be positive.", name);
+        }
+
+        let id = match self.ids.get(name) {
+            Some(&id) => id,
+            None => {
+                let id = self.names.len();
+                self.names.push(name.to_string());
+                self.ids.insert(name.to_string(), id);
+                self.reactions.push(None);
+                id
+            }
+        };
+
+        Ok((quantity, id))
+    }
+
+    fn build(self) -> Result<Reactions, Error> {
+        for (name, reaction) in self.names.iter().zip(&self.reactions) {
+            if name != ORE && reaction.is_none() {
+                bail!("No reaction produces {}.", name);
+            }
+        }
+
+        // Kahn's algorithm, starting from the chemicals nothing consumes.
+        let mut consumers = vec![0; self.names.len()];
+        for reaction in self.reactions.iter().flatten() {
+            for &(input, _) in &reaction.inputs {
+                consumers[input] += 1;
+            }
+        }
+
+        let mut ready = (0..self.names.len())
+            .filter(|&id| consumers[id] == 0)
+            .collect::<Vec<_>>();
+        let mut order = Vec::with_capacity(self.names.len());
+
+        while let Some(id) = ready.pop() {
+            order.push(id);
+            for &(input, _) in self.reactions[id].iter().flat_map(|r| &r.inputs) {
+                consumers[input] -= 1;
+                if consumers[input] == 0 {
+                    ready.push(input);
+                }
+            }
+        }
+
+        if order.len() != self.names.len() {
+            bail!("Reactions contain a cycle.");
+        }
+
+        Ok(Reactions {
+            names: self.names,
+            ids: self.ids,
+            reactions: self.reactions,
+            order,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    #[ignore]
-    fn test_dayxx() {}
+    fn test_day14() {
+        let test_cases = &[
+            // input, expected1, expected2
+            (
+                "10 ORE => 10 A\n1 ORE => 1 B\n7 A, 1 B => 1 C\n7 A, 1 C => 1 D\n7 A, 1 D => 1 E\n7 A, 1 E => 1 FUEL",
+                31,
+                None,
+            ),
+            (
+                "157 ORE => 5 NZVS\n165 ORE => 6 DCFZ\n44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL\n12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ\n179 ORE => 7 PSHF\n177 ORE => 5 HKGWZ\n7 DCFZ, 7 PSHF => 2 XJWVT\n165 ORE => 2 GPVTF\n3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT",
+                13312,
+                Some(82892753),
+            ),
+            (
+                "2 VPVL, 7 FWMGM, 2 CXFTF, 11 MNCFX => 1 STKFG\n17 NVRVD, 3 JNWZP => 8 VPVL\n53 STKFG, 6 MNCFX, 46 VJHF, 81 HVMC, 68 CXFTF, 25 GNMV => 1 FUEL\n22 VJHF, 37 MNCFX => 5 FWMGM\n139 ORE => 4 NVRVD\n144 ORE => 7 JNWZP\n5 MNCFX, 7 RFSQX, 2 FWMGM, 2 VPVL, 19 CXFTF => 3 HVMC\n5 VJHF, 7 MNCFX, 9 VPVL, 37 CXFTF => 6 GNMV\n145 ORE => 6 MNCFX\n1 NVRVD => 8 CXFTF\n1 VJHF, 6 MNCFX => 4 RFSQX\n176 ORE => 6 VJHF",
+                180697,
+                Some(5586022),
+            ),
+        ];
+
+        for (input, expected1, expected2) in test_cases {
+            let reactions = input.parse::<Reactions>().unwrap();
+
+            assert_eq!(reactions.ore_for(FUEL, 1).unwrap(), *expected1);
+            if let Some(expected2) = expected2 {
+                assert_eq!(reactions.max_produced(FUEL, CARGO_ORE).unwrap(), *expected2);
+            }
+
+            let output = reactions.to_string();
+            assert_eq!(output.parse::<Reactions>().unwrap().to_string(), output);
+        }
+
+        let reactions = test_cases[0].0.parse::<Reactions>().unwrap();
+        let production = reactions.produce("C", 1).unwrap();
+        assert_eq!(production.ore, 11);
+        assert_eq!(production.leftovers.get("A"), Some(&3));
+        assert_eq!(reactions.ore_for("A", 11).unwrap(), 20);
+        assert_eq!(reactions.ore_for(ORE, 5).unwrap(), 5);
+        assert_eq!(reactions.max_produced("B", 10).unwrap(), 10);
+        assert!(reactions.ore_for("Z", 1).is_err());
+
+        // Making 2^64 - 1 A takes 2^63 reactions, which make 2^64 A.
+        let reactions = "1 ORE => 2 A\n1 A => 1 FUEL".parse::<Reactions>().unwrap();
+        let e = reactions.ore_for(FUEL, u64::MAX).unwrap_err();
+        assert!(e.to_string().starts_with("Too much A "), "{}", e);
+        let reactions = "1 ORE => 1 A\n2 A => 1 FUEL".parse::<Reactions>().unwrap();
+        let e = reactions.ore_for(FUEL, u64::MAX).unwrap_err();
+        assert!(e.to_string().starts_with("Too much A "), "{}", e);
+        let reactions = "2 ORE => 1 FUEL".parse::<Reactions>().unwrap();
+        let e = reactions.ore_for(FUEL, u64::MAX).unwrap_err();
+        assert!(e.to_string().starts_with("Too much ORE "), "{}", e);
+        assert_eq!(reactions.chemicals().next(), Some(FUEL));
+        assert_eq!(reactions.chemicals().last(), Some(ORE));
+
+        let bad_inputs = &[
+            "1 ORE => 1 A\n1 ORE => 1 A",
+            "1 A => 1 ORE",
+            "1 ORE => 1 A\n1 B => 1 C",
+            "1 ORE, 1 B => 1 A\n1 A => 1 B",
+            "1 ORE => A",
+            "0 ORE => 1 A",
+            "1 ORE 1 A",
+        ];
+        for input in bad_inputs {
+            assert!(input.parse::<Reactions>().is_err(), "{:?}", input);
+        }
+
+        crate::utils::tests::test_full_problem(14, run, "374457", "3568888");
+    }
 }
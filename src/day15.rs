@@ -2,25 +2,57 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::convert::TryInto;
 
-use crate::computer::{ComputerST, Queue, Rom, State};
+use crate::computer::{ComputerST, Queue, State};
 use crate::error::Error;
+use crate::rom::Rom;
 use crate::utils::Vec2;
 
 type Point = Vec2<i64>;
 
 const ORIGIN: Point = Point::new(0, 0);
 
+pub type Input = Area;
+
+/// The area explored by the repair droid.
+pub struct Area {
+    graph: HashMap<Point, HashSet<Point>>,
+    oxygen: Point,
+    /// Fewest moves from the droid's starting point to the oxygen system.
+    distance: usize,
+}
+
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
+where
+    R: std::io::BufRead,
+{
+    let area = parse(reader)?;
+
+    Ok((part_one(&area)?, part_two(&area)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
 where
     R: std::io::BufRead,
 {
     let rom = Rom::from_reader(reader)?;
-    let mut droid = Droid::new(rom);
-    let (oxygen, answer1) = droid.run()?;
+    let mut droid = Droid::new(&rom);
+    let (oxygen, distance) = droid.run()?;
+
+    Ok(Area {
+        graph: droid.graph,
+        oxygen,
+        distance,
+    })
+}
+
+pub fn part_one(area: &Input) -> Result<String, Error> {
+    Ok(area.distance.to_string())
+}
 
-    let answer2 = largest_layer(oxygen, &droid.graph);
+pub fn part_two(area: &Input) -> Result<String, Error> {
+    let answer = largest_layer(area.oxygen, &area.graph);
 
-    Ok((answer1.to_string(), answer2.to_string()))
+    Ok(answer.to_string())
 }
 
 fn largest_layer(start: Point, graph: &HashMap<Point, HashSet<Point>>) -> usize {
@@ -95,7 +127,7 @@ impl Droid {
             self.visited.insert(parent);
             self.computer = computer;
 
-            for (point, direction) in surrounding_points(parent).into_iter() {
+            for (point, direction) in surrounding_points(parent).iter() {
                 if !self.visited.contains(point) {
                     self.computer.input_mut().enqueue(*direction as i64);
 
@@ -107,8 +139,8 @@ impl Droid {
                     match status {
                         Status::Wall => {}
                         Status::Move | Status::Oxygen => {
-                            self.graph.entry(parent).or_insert_with(HashSet::new).insert(*point);
-                            self.graph.entry(*point).or_insert_with(HashSet::new).insert(parent);
+                            self.graph.entry(parent).or_default().insert(*point);
+                            self.graph.entry(*point).or_default().insert(parent);
 
                             let layer = self.layers.get(&parent).unwrap() + 1;
                             self.layers.insert(*point, layer);
@@ -1,15 +1,264 @@
 use crate::error::Error;
 
+const BASE_PATTERN: [i64; 4] = [0, 1, 0, -1];
+const PHASES: usize = 100;
+const REPEAT: usize = 10_000;
+const MESSAGE_LEN: usize = 8;
+const OFFSET_LEN: usize = 7;
+
+pub type Input = Signal;
+
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    Ok((String::new(), String::new()))
+    let signal = parse(reader)?;
+
+    Ok((part_one(&signal)?, part_two(&signal)?))
+}
+
+pub fn parse<R>(mut reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    let mut buf = String::new();
+    reader.read_to_string(&mut buf)?;
+
+    Signal::new(buf.trim())
+}
+
+pub fn part_one(signal: &Input) -> Result<String, Error> {
+    let output = signal.output();
+    Ok(to_string(&output[..MESSAGE_LEN.min(output.len())]))
+}
+
+pub fn part_two(signal: &Input) -> Result<String, Error> {
+    if signal.digits.len() < OFFSET_LEN {
+        bail!(
+            "Signal has {} digit(s), but the message offset needs {}.",
+            signal.digits.len(),
+            OFFSET_LEN
+        );
+    }
+    let offset = signal.digits[..OFFSET_LEN]
+        .iter()
+        .fold(0, |acc, &d| acc * 10 + d as usize);
+
+    let message = signal.message(REPEAT, offset, MESSAGE_LEN)?;
+    Ok(to_string(&message))
+}
+
+/// A signal to be cleaned up by repeated phases of Flawed Frequency
+/// Transmission.
+///
+/// Each phase replaces every digit with the ones digit of a weighted sum of
+/// the whole signal. For output digit `i`, each element of the pattern is
+/// repeated `i + 1` times and the first weight is skipped.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Signal {
+    digits: Vec<u8>,
+    pattern: Vec<i64>,
+    phases: usize,
+}
+
+impl Signal {
+    /// Creates a signal from a string of digits, using the puzzle's base
+    /// pattern and 100 phases.
+    pub fn new(s: &str) -> Result<Self, Error> {
+        let digits = s
+            .bytes()
+            .map(|b| match b {
+                b'0'..=b'9' => Ok(b - b'0'),
+                _ => bail!("Invalid digit {:?} in signal.", b as char),
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        if digits.is_empty() {
+            bail!("Signal is empty.");
+        }
+
+        Ok(Self {
+            digits,
+            pattern: BASE_PATTERN.to_vec(),
+            phases: PHASES,
+        })
+    }
+
+    pub fn with_pattern(mut self, pattern: Vec<i64>) -> Result<Self, Error> {
+        if pattern.is_empty() {
+            bail!("Pattern must not be empty.");
+        }
+        self.pattern = pattern;
+        Ok(self)
+    }
+
+    pub fn with_phases(mut self, phases: usize) -> Self {
+        self.phases = phases;
+        self
+    }
+
+    pub fn digits(&self) -> &[u8] {
+        &self.digits
+    }
+
+    /// Returns the full signal after every phase.
+    ///
+    /// Within a phase the weights are constant over runs of `i + 1` digits, so
+    /// with prefix sums each run costs O(1) and output digit `i` costs
+    /// O(n / (i + 1)), for O(n log n) per phase.
+    pub fn output(&self) -> Vec<u8> {
+        let n = self.digits.len();
+        let mut digits = self.digits.clone();
+        let mut prefix = vec![0i64; n + 1];
+
+        for _ in 0..self.phases {
+            for (k, &d) in digits.iter().enumerate() {
+                prefix[k + 1] = prefix[k] + d as i64;
+            }
+
+            for (i, digit) in digits.iter_mut().enumerate() {
+                let width = i + 1;
+                let mut sum = 0;
+
+                // Run `j` covers signal positions `j * width - 1` up to
+                // `(j + 1) * width - 1`, the first being one short.
+                for (j, &weight) in self.pattern.iter().cycle().enumerate() {
+                    let lo = (j * width).saturating_sub(1);
+                    if lo >= n {
+                        break;
+                    }
+                    if weight != 0 {
+                        let hi = ((j + 1) * width - 1).min(n);
+                        sum += weight * (prefix[hi] - prefix[lo]);
+                    }
+                }
+
+                *digit = (sum.abs() % 10) as u8;
+            }
+        }
+
+        digits
+    }
+
+    /// Returns the `len` digits starting at `offset` of the output for the
+    /// signal repeated `repeat` times.
+    ///
+    /// This only works when the offset is in the second half of the repeated
+    /// signal. There, every digit before `i` has weight `pattern[0]`, which
+    /// must be zero, and every digit from `i` on has weight `pattern[1]`, so
+    /// each phase is a running sum from the end.
+    pub fn message(&self, repeat: usize, offset: usize, len: usize) -> Result<Vec<u8>, Error> {
+        let n = self.digits.len();
+        let total = n * repeat;
+
+        if offset + len > total {
+            bail!(
+                "Message at offset {} runs past the end of the {} digit signal.",
+                offset,
+                total
+            );
+        }
+        if 2 * offset + 1 < total {
+            bail!(
+                "Message offset {} is not in the second half of the {} digit signal.",
+                offset,
+                total
+            );
+        }
+        if self.pattern[0] != 0 {
+            bail!("Pattern must start with a zero to use the message offset.");
+        }
+
+        let weight = self.pattern[1 % self.pattern.len()].unsigned_abs() % 10;
+        let mut digits = (offset..total)
+            .map(|k| self.digits[k % n])
+            .collect::<Vec<_>>();
+
+        for _ in 0..self.phases {
+            let mut sum = 0;
+            for digit in digits.iter_mut().rev() {
+                sum = (sum + *digit as u64) % 10;
+                *digit = (sum * weight % 10) as u8;
+            }
+        }
+
+        digits.truncate(len);
+        Ok(digits)
+    }
+}
+
+impl std::str::FromStr for Signal {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+fn to_string(digits: &[u8]) -> String {
+    digits.iter().map(|&d| (b'0' + d) as char).collect()
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    #[ignore]
-    fn test_dayxx() {}
+    fn test_day16() {
+        let signal = Signal::new("12345678").unwrap();
+        let expected = &["48226158", "34040438", "03415518", "01029498"];
+        for (phases, expected) in expected.iter().enumerate() {
+            let output = signal.clone().with_phases(phases + 1).output();
+            assert_eq!(to_string(&output), *expected);
+        }
+
+        let test_cases = &[
+            // input, expected
+            ("80871224585914546619083218645595", "24176176"),
+            ("19617804207202209144916044189917", "73745418"),
+            ("69317163492948606335995924319873", "52432133"),
+        ];
+        for (input, expected) in test_cases {
+            let signal = input.parse::<Signal>().unwrap();
+            assert_eq!(part_one(&signal).unwrap(), *expected);
+        }
+
+        let test_cases = &[
+            // input, expected
+            ("03036732577212944063491565474664", "84462026"),
+            ("02935109699940807407585447034323", "78725270"),
+            ("03081770884921959731165446850517", "53553731"),
+        ];
+        for (input, expected) in test_cases {
+            let signal = input.parse::<Signal>().unwrap();
+            assert_eq!(part_two(&signal).unwrap(), *expected);
+        }
+
+        // The shortcut agrees with the full evaluation in the second half,
+        // including for other patterns.
+        for pattern in &[vec![0, 1, 0, -1], vec![0, 3], vec![0, -2, 1]] {
+            let signal = "5973145820364758"
+                .parse::<Signal>()
+                .unwrap()
+                .with_pattern(pattern.clone())
+                .unwrap()
+                .with_phases(7);
+            let output = signal.output();
+            let message = signal.message(1, 8, 8).unwrap();
+            assert_eq!(message, &output[8..]);
+        }
+
+        let signal = Signal::new("12345678").unwrap();
+        assert!(signal.message(1, 2, 4).is_err());
+        assert!(signal.message(1, 6, 4).is_err());
+        assert!(signal.clone().with_pattern(vec![]).is_err());
+        assert!(signal
+            .with_pattern(vec![1, 0])
+            .unwrap()
+            .message(1, 4, 4)
+            .is_err());
+        assert!(Signal::new("12a4").is_err());
+
+        crate::utils::tests::test_full_problem(16, run, "90744714", "82994322");
+    }
 }
@@ -1,15 +1,505 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::computer::{ComputerST, Queue, State};
 use crate::error::Error;
+use crate::patch::Patch;
+use crate::rom::Rom;
+
+/// Longest a movement routine or function may be, not counting the newline.
+const MAX_LEN: usize = 20;
+const NUM_FUNCTIONS: usize = 3;
+
+pub type Input = Robot;
+
+/// The ASCII program, along with the scaffolding its cameras see.
+pub struct Robot {
+    rom: Rom,
+    scaffold: Scaffold,
+}
 
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    Ok((String::new(), String::new()))
+    let robot = parse(reader)?;
+
+    Ok((part_one(&robot)?, part_two(&robot)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    let rom = Rom::from_reader(reader)?;
+    let scaffold = camera_view(&rom)?.parse::<Scaffold>()?;
+
+    Ok(Robot { rom, scaffold })
+}
+
+pub fn part_one(robot: &Input) -> Result<String, Error> {
+    Ok(robot.scaffold.alignment().to_string())
+}
+
+pub fn part_two(robot: &Input) -> Result<String, Error> {
+    let path = robot.scaffold.path();
+    let routines = Routines::compress(&path, MAX_LEN)
+        .ok_or_else(|| error!("Unable to split the path into movement functions."))?;
+
+    Ok(collect_dust(&robot.rom, &routines)?.to_string())
+}
+
+/// Sets address 0 to 2 so the vacuum robot wakes up and takes a routine.
+pub fn wake_up() -> Patch {
+    Patch::new(vec![(0, 2)])
+}
+
+/// Runs the ASCII program without waking the robot, returning what its
+/// cameras see.
+pub fn camera_view<R>(rom: R) -> Result<String, Error>
+where
+    R: AsRef<[i64]>,
+{
+    let mut computer = ComputerST::new(rom);
+    let mut view = String::new();
+
+    loop {
+        match computer.step()? {
+            State::Done => break,
+            State::NeedsInput => bail!("Camera unexpectedly asked for input."),
+            State::HasOutput => {
+                let val = computer.output_mut().dequeue()?;
+                view.push(ascii(val)?);
+            }
+        }
+    }
+
+    Ok(view)
+}
+
+/// Wakes up the robot, feeds it `routines` and returns the dust it reports
+/// collecting.
+pub fn collect_dust<R>(rom: R, routines: &Routines) -> Result<i64, Error>
+where
+    R: AsRef<[i64]>,
+{
+    let mut computer = ComputerST::new(rom);
+    computer.patch(&wake_up())?;
+
+    // No continuous video feed.
+    let input = format!("{}n\n", routines);
+    for b in input.bytes() {
+        computer.input_mut().enqueue(b as i64);
+    }
+
+    let mut last = None;
+    loop {
+        match computer.step()? {
+            State::Done => break,
+            State::NeedsInput => bail!("Robot asked for more input than the routines."),
+            State::HasOutput => last = Some(computer.output_mut().dequeue()?),
+        }
+    }
+
+    match last {
+        Some(dust) if dust > 127 => Ok(dust),
+        Some(_) => bail!("Robot finished without reporting any dust."),
+        None => bail!("Robot produced no output."),
+    }
+}
+
+fn ascii(val: i64) -> Result<char, Error> {
+    if (0..128).contains(&val) {
+        Ok(val as u8 as char)
+    } else {
+        bail!("Expected ASCII output, but got {}.", val)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    fn left(self) -> Self {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Right => Direction::Up,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Down,
+        }
+    }
+
+    fn right(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+}
+
+/// The scaffolding as seen by the cameras, along with the vacuum robot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Scaffold {
+    grid: Vec<bool>,
+    rows: usize,
+    cols: usize,
+    robot: (usize, usize),
+    direction: Direction,
+}
+
+impl Scaffold {
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns whether there is scaffolding at row `y`, column `x`.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < self.cols && y < self.rows && self.grid[y * self.cols + x]
+    }
+
+    /// Returns the `(x, y)` of every scaffold with scaffolding on all four
+    /// sides.
+    pub fn intersections(&self) -> Vec<(usize, usize)> {
+        let mut intersections = Vec::new();
+
+        for y in 1..self.rows.saturating_sub(1) {
+            for x in 1..self.cols.saturating_sub(1) {
+                if self.get(x, y)
+                    && self.get(x - 1, y)
+                    && self.get(x + 1, y)
+                    && self.get(x, y - 1)
+                    && self.get(x, y + 1)
+                {
+                    intersections.push((x, y));
+                }
+            }
+        }
+
+        intersections
+    }
+
+    /// Returns the sum of the intersections' alignment parameters.
+    pub fn alignment(&self) -> usize {
+        self.intersections().iter().map(|(x, y)| x * y).sum()
+    }
+
+    /// Traces the scaffolding from the robot, going straight through
+    /// intersections and turning only at corners, until it reaches a dead end.
+    pub fn path(&self) -> Vec<Move> {
+        let mut path = Vec::new();
+        let mut position = self.robot;
+        let mut direction = self.direction;
+
+        loop {
+            let mut steps = 0;
+            while let Some(next) = self.next(position, direction) {
+                position = next;
+                steps += 1;
+            }
+            if steps > 0 {
+                path.push(Move::Forward(steps));
+            }
+
+            if self.next(position, direction.left()).is_some() {
+                direction = direction.left();
+                path.push(Move::Left);
+            } else if self.next(position, direction.right()).is_some() {
+                direction = direction.right();
+                path.push(Move::Right);
+            } else {
+                break;
+            }
+        }
+
+        path
+    }
+
+    /// Returns the neighbouring scaffold in `direction`, if there is one.
+    fn next(&self, (x, y): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let (x, y) = match direction {
+            Direction::Up => (x, y.checked_sub(1)?),
+            Direction::Right => (x + 1, y),
+            Direction::Down => (x, y + 1),
+            Direction::Left => (x.checked_sub(1)?, y),
+        };
+
+        if self.get(x, y) {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for Scaffold {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+
+        let rows = lines.len();
+        let cols = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let mut grid = vec![false; rows * cols];
+        let mut robot = None;
+
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let direction = match c {
+                    '.' => continue,
+                    '#' => None,
+                    '^' => Some(Direction::Up),
+                    '>' => Some(Direction::Right),
+                    'v' => Some(Direction::Down),
+                    '<' => Some(Direction::Left),
+                    'X' => bail!("The robot has fallen off the scaffolding."),
+                    _ => bail!("Invalid character {:?} in camera view.", c),
+                };
+
+                grid[y * cols + x] = true;
+
+                if let Some(direction) = direction {
+                    if robot.is_some() {
+                        bail!("Camera view shows more than one robot.");
+                    }
+                    robot = Some(((x, y), direction));
+                }
+            }
+        }
+
+        let (robot, direction) =
+            robot.ok_or_else(|| error!("Camera view does not show the robot."))?;
+
+        Ok(Self {
+            grid,
+            rows,
+            cols,
+            robot,
+            direction,
+        })
+    }
+}
+
+/// A single movement command for the vacuum robot.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Move {
+    Left,
+    Right,
+    Forward(usize),
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Move::Left => write!(f, "L"),
+            Move::Right => write!(f, "R"),
+            Move::Forward(steps) => write!(f, "{}", steps),
+        }
+    }
+}
+
+/// A main movement routine calling up to three movement functions, `A`, `B`
+/// and `C`, each a list of moves.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Routines {
+    pub main: Vec<usize>,
+    pub functions: Vec<Vec<Move>>,
+}
+
+impl Routines {
+    /// Splits `path` into a main routine and functions, each at most
+    /// `max_len` characters when written out, or `None` if it can't be done.
+    ///
+    /// A forward move may be split between functions, so `12` can end one
+    /// function as `6` and start the next as another `6`.
+    pub fn compress(path: &[Move], max_len: usize) -> Option<Self> {
+        // Search over single steps, so that a function can end anywhere.
+        let steps = path
+            .iter()
+            .flat_map(|&m| match m {
+                Move::Forward(n) => std::iter::repeat(Move::Forward(1)).take(n),
+                m => std::iter::repeat(m).take(1),
+            })
+            .collect::<Vec<_>>();
+
+        let mut routines = Routines::default();
+        if !routines.search(&steps, max_len) {
+            return None;
+        }
+        for function in &mut routines.functions {
+            *function = combine(function.iter().copied());
+        }
+
+        Some(routines)
+    }
+
+    /// Returns the full path the robot follows, with consecutive forward
+    /// moves combined.
+    pub fn expand(&self) -> Vec<Move> {
+        combine(
+            self.main
+                .iter()
+                .flat_map(|&i| self.functions[i].iter().copied()),
+        )
+    }
+
+    /// Depth first search over ways to cover the rest of `path`, either with
+    /// an existing function or by defining a new one. While searching, both
+    /// `path` and the functions are single steps.
+    fn search(&mut self, path: &[Move], max_len: usize) -> bool {
+        if path.is_empty() {
+            return true;
+        }
+        if encoded_len(self.main.len() + 1, |_| 1) > max_len {
+            return false;
+        }
+
+        for i in 0..self.functions.len() {
+            if path.starts_with(&self.functions[i]) {
+                let len = self.functions[i].len();
+                self.main.push(i);
+                if self.search(&path[len..], max_len) {
+                    return true;
+                }
+                self.main.pop();
+            }
+        }
+
+        if self.functions.len() < NUM_FUNCTIONS {
+            let i = self.functions.len();
+            self.main.push(i);
+
+            for len in 1..=path.len() {
+                let function = &path[..len];
+                let combined = combine(function.iter().copied());
+                if encoded_len(combined.len(), |j| combined[j].to_string().len()) > max_len {
+                    break;
+                }
+                self.functions.push(function.to_vec());
+                if self.search(&path[len..], max_len) {
+                    return true;
+                }
+                self.functions.pop();
+            }
+
+            self.main.pop();
+        }
+
+        false
+    }
+}
+
+/// Joins consecutive forward moves into one.
+fn combine<I>(moves: I) -> Vec<Move>
+where
+    I: IntoIterator<Item = Move>,
+{
+    let mut combined = Vec::new();
+
+    for m in moves {
+        match (combined.last_mut(), m) {
+            (Some(Move::Forward(steps)), Move::Forward(more)) => *steps += more,
+            _ => combined.push(m),
+        }
+    }
+
+    combined
+}
+
+/// Returns the length of `n` comma separated items, item `i` having length
+/// `len(i)`.
+fn encoded_len<F>(n: usize, len: F) -> usize
+where
+    F: Fn(usize) -> usize,
+{
+    (0..n).map(len).sum::<usize>() + n.saturating_sub(1)
+}
+
+impl fmt::Display for Routines {
+    /// Writes the main routine then each function, one per line, as the robot
+    /// expects them.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let main = self
+            .main
+            .iter()
+            .map(|&i| ((b'A' + i as u8) as char).to_string())
+            .collect::<Vec<_>>();
+        writeln!(f, "{}", main.join(","))?;
+
+        for i in 0..NUM_FUNCTIONS {
+            let function = self
+                .functions
+                .get(i)
+                .map(|function| function.iter().map(Move::to_string).collect::<Vec<_>>())
+                .unwrap_or_default();
+            writeln!(f, "{}", function.join(","))?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    #[ignore]
-    fn test_dayxx() {}
+    fn test_day17() {
+        let input = "..#..........\n..#..........\n#######...###\n#.#...#...#.#\n#############\n..#...#...#..\n..#####...^..\n";
+        let scaffold = input.parse::<Scaffold>().unwrap();
+        assert_eq!(scaffold.intersections().len(), 4);
+        assert_eq!(scaffold.alignment(), 76);
+
+        let input = "#######...#####\n#.....#...#...#\n#.....#...#...#\n......#...#...#\n......#...###.#\n......#.....#.#\n^########...#.#\n......#.#...#.#\n......#########\n........#...#..\n....#########..\n....#...#......\n....#...#......\n....#...#......\n....#####......\n";
+        let scaffold = input.parse::<Scaffold>().unwrap();
+        let path = scaffold.path();
+        let joined = path.iter().map(Move::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            joined.join(","),
+            "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2"
+        );
+
+        let routines = Routines::compress(&path, MAX_LEN).unwrap();
+        assert_eq!(routines.expand(), path);
+        for line in routines.to_string().lines() {
+            assert!(line.len() <= MAX_LEN, "{:?}", line);
+        }
+        assert!(Routines::compress(&path, 4).is_none());
+
+        // Only compresses by splitting runs: A = R,11  B = 6,R,2,L,3  C = R,19,R,2
+        let path = "R,11,R,17,R,2,L,3,R,17,R,2,L,3,R,19,R,2"
+            .split(',')
+            .map(|m| match m {
+                "L" => Move::Left,
+                "R" => Move::Right,
+                n => Move::Forward(n.parse().unwrap()),
+            })
+            .collect::<Vec<_>>();
+        let routines = Routines::compress(&path, 11).unwrap();
+        assert_eq!(routines.expand(), path);
+        assert_eq!(
+            routines.to_string(),
+            "A,A,B,A,B,C\nR,11\n6,R,2,L,3\nR,19,R,2\n"
+        );
+
+        assert!("#.#\n#.#\n".parse::<Scaffold>().is_err());
+        assert!("^.^\n#.#\n".parse::<Scaffold>().is_err());
+        assert!("#X#\n".parse::<Scaffold>().is_err());
+
+        crate::utils::tests::test_full_problem(17, run, "9876", "1234055");
+    }
 }
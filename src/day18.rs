@@ -1,15 +1,269 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
 use crate::error::Error;
+use crate::maze::Maze;
+
+const ENTRANCE: u8 = b'@';
+const WALL: u8 = b'#';
+
+pub type Input = Maze;
 
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    Ok((String::new(), String::new()))
+    let maze = parse(reader)?;
+
+    Ok((part_one(&maze)?, part_two(&maze)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Maze::from_reader(reader)
+}
+
+pub fn part_one(maze: &Input) -> Result<String, Error> {
+    let answer = Vault::new(maze)?.shortest()?;
+
+    Ok(answer.to_string())
+}
+
+pub fn part_two(maze: &Input) -> Result<String, Error> {
+    let answer = Vault::new(&split(maze)?)?.shortest()?;
+
+    Ok(answer.to_string())
+}
+
+/// Walls off the single entrance and the cells around it, leaving an entrance
+/// in each of the four diagonal cells.
+pub fn split(maze: &Maze) -> Result<Maze, Error> {
+    let entrances = maze.find(ENTRANCE);
+    let (x, y) = match entrances[..] {
+        [(x, y)] if x > 0 && y > 0 => (x, y),
+        [_] => bail!("The entrance is on the edge of the vault."),
+        _ => bail!("Expected one entrance, but found {}.", entrances.len()),
+    };
+
+    let mut maze = maze.clone();
+    for dy in 0..3 {
+        for dx in 0..3 {
+            let point = (x + dx - 1, y + dy - 1);
+            let cell = if dx == 1 || dy == 1 { WALL } else { ENTRANCE };
+            maze.set(point, cell)?;
+        }
+    }
+
+    Ok(maze)
+}
+
+/// A vault of keys and the doors they open, explored by one robot per
+/// entrance.
+///
+/// Nodes are the entrances followed by the keys. Each node stores the keys
+/// reachable from it, with the distance and the doors in the way, so the
+/// search over which keys have been collected never has to walk the maze.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Vault {
+    num_robots: usize,
+    /// Bit `i` is set for the key to door `A + i`, for every key in the vault.
+    all_keys: u32,
+    /// The keys reachable from each node.
+    edges: Vec<Vec<Edge>>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Edge {
+    /// Node of the key, and the bit it sets.
+    key: usize,
+    bit: u32,
+    dist: usize,
+    /// Keys needed first, whether for doors or for keys along the way.
+    doors: u32,
+}
+
+impl Vault {
+    pub fn new(maze: &Maze) -> Result<Self, Error> {
+        let mut nodes = maze.find(ENTRANCE);
+        let num_robots = nodes.len();
+        if num_robots == 0 {
+            bail!("The vault has no entrance.");
+        }
+
+        let mut keys = maze
+            .cells()
+            .filter(|(_, cell)| cell.is_ascii_lowercase())
+            .collect::<Vec<_>>();
+        keys.sort_by_key(|&(_, cell)| cell);
+        for pair in keys.windows(2) {
+            if pair[0].1 == pair[1].1 {
+                bail!("Key {:?} appears more than once.", pair[0].1 as char);
+            }
+        }
+        nodes.extend(keys.iter().map(|&(point, _)| point));
+
+        let all_keys = keys.iter().fold(0, |acc, &(_, cell)| acc | bit(cell));
+        let ids = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, &point)| (point, i))
+            .collect::<HashMap<_, _>>();
+
+        let edges = nodes
+            .iter()
+            .map(|&start| {
+                // Walking over a key picks it up, so a path through other
+                // keys requires them as if they were doors. The direct routes
+                // to those keys are edges of their own.
+                maze.bfs(start, 0, |_, cell, &doors| match cell {
+                    WALL => None,
+                    b'A'..=b'Z' | b'a'..=b'z' => Some(doors | bit(cell.to_ascii_lowercase())),
+                    _ => Some(doors),
+                })
+                .into_iter()
+                .filter(|&(point, dist, _)| {
+                    dist > 0 && maze.get(point).unwrap().is_ascii_lowercase()
+                })
+                .map(|(point, dist, doors)| {
+                    let bit = bit(maze.get(point).unwrap());
+                    Edge {
+                        key: ids[&point],
+                        bit,
+                        dist,
+                        doors: doors & !bit,
+                    }
+                })
+                .collect()
+            })
+            .collect();
+
+        Ok(Self {
+            num_robots,
+            all_keys,
+            edges,
+        })
+    }
+
+    /// Returns the fewest total steps for the robots to collect every key,
+    /// using Dijkstra's algorithm over the robots' positions and the keys
+    /// collected so far.
+    pub fn shortest(&self) -> Result<usize, Error> {
+        let start = State {
+            robots: (0..self.num_robots).collect(),
+            keys: 0,
+        };
+
+        let mut best = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        best.insert(start.clone(), 0);
+        heap.push(Reverse((0, start)));
+
+        while let Some(Reverse((dist, state))) = heap.pop() {
+            if state.keys == self.all_keys {
+                return Ok(dist);
+            }
+            if best.get(&state).is_some_and(|&other| other < dist) {
+                continue;
+            }
+
+            for (i, &node) in state.robots.iter().enumerate() {
+                for edge in &self.edges[node] {
+                    if state.keys & edge.bit != 0 || edge.doors & !state.keys != 0 {
+                        continue;
+                    }
+
+                    let mut next = state.clone();
+                    next.robots[i] = edge.key;
+                    next.keys |= edge.bit;
+                    let dist = dist + edge.dist;
+
+                    let shorter = match best.get(&next) {
+                        Some(&other) => dist < other,
+                        None => true,
+                    };
+                    if shorter {
+                        best.insert(next.clone(), dist);
+                        heap.push(Reverse((dist, next)));
+                    }
+                }
+            }
+        }
+
+        bail!("Unable to collect every key.")
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct State {
+    /// The node each robot is at.
+    robots: Vec<usize>,
+    keys: u32,
+}
+
+fn bit(key: u8) -> u32 {
+    1 << (key - b'a')
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    #[ignore]
-    fn test_dayxx() {}
+    fn test_day18() {
+        let test_cases = &[
+            // input, expected
+            ("#########\n#b.A.@.a#\n#########", 8),
+            (
+                "########################\n#f.D.E.e.C.b.A.@.a.B.c.#\n######################.#\n#d.....................#\n########################",
+                86,
+            ),
+            (
+                "########################\n#...............b.C.D.f#\n#.######################\n#.....@.a.B.c.d.A.e.F.g#\n########################",
+                132,
+            ),
+            (
+                "#################\n#i.G..c...e..H.p#\n########.########\n#j.A..b...f..D.o#\n########@########\n#k.E..a...g..B.n#\n########.########\n#l.F..d...h..C.m#\n#################",
+                136,
+            ),
+            (
+                "########################\n#@..............ac.GI.b#\n###d#e#f################\n###A#B#C################\n###g#h#i################\n########################",
+                81,
+            ),
+        ];
+        for (input, expected) in test_cases {
+            let maze = input.parse::<Maze>().unwrap();
+            assert_eq!(Vault::new(&maze).unwrap().shortest().unwrap(), *expected);
+        }
+
+        let test_cases = &[
+            // input, expected
+            ("#######\n#a.#Cd#\n##...##\n##.@.##\n##...##\n#cB#Ab#\n#######", 8),
+            (
+                "#############\n#DcBa.#.GhKl#\n#.###@#@#I###\n#e#d#####j#k#\n###C#@#@###J#\n#fEbA.#.FgHi#\n#############",
+                32,
+            ),
+            (
+                "#############\n#g#f.D#..h#l#\n#F###e#E###.#\n#dCba@#@BcIJ#\n#############\n#nK.L@#@G...#\n#M###N#H###.#\n#o#m..#i#jk.#\n#############",
+                72,
+            ),
+        ];
+        for (input, expected) in test_cases {
+            let mut maze = input.parse::<Maze>().unwrap();
+            if maze.find(ENTRANCE).len() == 1 {
+                maze = split(&maze).unwrap();
+            }
+            assert_eq!(Vault::new(&maze).unwrap().shortest().unwrap(), *expected);
+        }
+
+        for input in &["#####\n@aA.#\n#####", "#####\n#.aA@\n#####", "#@#@#"] {
+            let maze = input.parse::<Maze>().unwrap();
+            assert!(split(&maze).is_err(), "{:?}", input);
+        }
+        let maze = "#####\n#@Ab#\n#####".parse::<Maze>().unwrap();
+        assert!(Vault::new(&maze).unwrap().shortest().is_err());
+
+        crate::utils::tests::test_full_problem(18, run, "4420", "2128");
+    }
 }
@@ -1,15 +1,191 @@
+use crate::computer::{ComputerST, Queue, State};
 use crate::error::Error;
+use crate::rom::Rom;
+
+const AREA: u64 = 50;
+const SHIP: u64 = 100;
+/// How far along a row to look for the beam before giving up.
+const MAX_WIDTH: u64 = 100_000;
+/// How far from the emitter to look for the square before giving up.
+const MAX_ROWS: u64 = 100_000;
+
+pub type Input = Rom;
 
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    Ok((String::new(), String::new()))
+    let rom = parse(reader)?;
+
+    Ok((part_one(&rom)?, part_two(&rom)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Rom::from_reader(reader)
+}
+
+pub fn part_one(rom: &Input) -> Result<String, Error> {
+    let mut drone = Drone::new(rom);
+    let answer = drone.count(AREA)?;
+
+    Ok(answer.to_string())
+}
+
+pub fn part_two(rom: &Input) -> Result<String, Error> {
+    let mut drone = Drone::new(rom);
+    let (x, y) = drone.closest_square(SHIP)?;
+
+    Ok((x * 10_000 + y).to_string())
+}
+
+/// Deploys drones to find out where the tractor beam pulls.
+///
+/// The program halts after every query, so each one needs a fresh computer.
+/// Rather than allocate one per query, a single computer is reset from the
+/// ROM each time.
+pub struct Drone {
+    rom: Vec<i64>,
+    computer: ComputerST,
+    queries: usize,
+}
+
+impl Drone {
+    pub fn new<R>(rom: R) -> Self
+    where
+        R: AsRef<[i64]>,
+    {
+        let rom = rom.as_ref().to_vec();
+        let computer = ComputerST::new(&rom);
+
+        Self {
+            rom,
+            computer,
+            queries: 0,
+        }
+    }
+
+    /// Returns whether the beam pulls at `(x, y)`.
+    pub fn pulled(&mut self, x: u64, y: u64) -> Result<bool, Error> {
+        self.computer.reset(&self.rom);
+        self.computer.input_mut().enqueue(x as i64);
+        self.computer.input_mut().enqueue(y as i64);
+        self.queries += 1;
+
+        match self.computer.step()? {
+            State::HasOutput => match self.computer.output_mut().dequeue()? {
+                0 => Ok(false),
+                1 => Ok(true),
+                val => bail!("Drone reported {} at ({}, {}).", val, x, y),
+            },
+            _ => bail!("Drone did not report back from ({}, {}).", x, y),
+        }
+    }
+
+    /// Returns the number of drone deployments so far.
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+
+    /// Returns the number of points pulled in the `size` by `size` area
+    /// closest to the emitter.
+    pub fn count(&mut self, size: u64) -> Result<usize, Error> {
+        let mut count = 0;
+
+        for y in 0..size {
+            for x in 0..size {
+                if self.pulled(x, y)? {
+                    count += 1;
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Returns the top left corner of the `size` by `size` square closest to
+    /// the emitter that fits entirely within the beam.
+    ///
+    /// The beam is a cone, so its left edge only moves right from one row to
+    /// the next. For each candidate bottom row, the square's bottom left
+    /// corner sits on that edge, and it fits if its top right corner is also
+    /// pulled. Only a few points per row are queried.
+    pub fn closest_square(&mut self, size: u64) -> Result<(u64, u64), Error> {
+        if size == 0 {
+            bail!("Square must have a positive size.");
+        }
+
+        // Near the emitter the beam can miss whole rows, but a row as tall as
+        // the square is far enough out to find it.
+        let mut y = size - 1;
+        let mut left = self.left_edge(0, y)?;
+
+        loop {
+            if self.pulled(left + size - 1, y + 1 - size)? {
+                return Ok((left, y + 1 - size));
+            }
+
+            y += 1;
+            if y > MAX_ROWS {
+                bail!(
+                    "Unable to fit a {0}x{0} square within {1} rows.",
+                    size,
+                    MAX_ROWS
+                );
+            }
+            left = self.left_edge(left, y)?;
+        }
+    }
+
+    /// Returns the first pulled point on row `y`, starting from `x`.
+    fn left_edge(&mut self, mut x: u64, y: u64) -> Result<u64, Error> {
+        let start = x;
+        while !self.pulled(x, y)? {
+            x += 1;
+            if x - start > MAX_WIDTH {
+                bail!("Unable to find the beam on row {}.", y);
+            }
+        }
+        Ok(x)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    #[ignore]
-    fn test_dayxx() {}
+    fn test_day19() {
+        let rom = std::fs::read_to_string("input/day19.txt")
+            .unwrap()
+            .parse::<Rom>()
+            .unwrap();
+        let mut drone = Drone::new(&rom);
+
+        // Reusing one computer gives the same answers as fresh ones.
+        for &(x, y) in &[(0, 0), (3, 4), (10, 12), (40, 49), (49, 40)] {
+            let mut computer = ComputerST::new(&rom);
+            computer.input_mut().enqueue(x as i64);
+            computer.input_mut().enqueue(y as i64);
+            computer.run().unwrap();
+            let expected = computer.output_mut().dequeue().unwrap() == 1;
+            assert_eq!(drone.pulled(x, y).unwrap(), expected);
+        }
+
+        // Tracking the edges needs far fewer queries than scanning.
+        let mut drone = Drone::new(&rom);
+        drone.closest_square(SHIP).unwrap();
+        assert!(drone.queries() < 10_000, "{}", drone.queries());
+
+        // A beam one point wide never fits a square.
+        let line = "3,100,3,101,8,100,101,102,4,102,99".parse::<Rom>().unwrap();
+        let mut drone = Drone::new(&line);
+        assert!(drone.pulled(7, 7).unwrap());
+        assert!(!drone.pulled(7, 8).unwrap());
+        assert!(drone.closest_square(2).is_err());
+
+        crate::utils::tests::test_full_problem(19, run, "112", "18261982");
+    }
 }
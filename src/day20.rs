@@ -1,15 +1,341 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
 use crate::error::Error;
+use crate::maze::{Maze, Point};
+
+const OPEN: u8 = b'.';
+const START: [u8; 2] = *b"AA";
+const END: [u8; 2] = *b"ZZ";
+/// Deepest level the recursive search will go before giving up on a path.
+const MAX_DEPTH: usize = 250;
+
+pub type Input = Donut;
 
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    Ok((String::new(), String::new()))
+    let donut = parse(reader)?;
+
+    Ok((part_one(&donut)?, part_two(&donut)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Donut::new(&Maze::from_reader(reader)?)
+}
+
+pub fn part_one(donut: &Input) -> Result<String, Error> {
+    Ok(donut.shortest()?.to_string())
+}
+
+pub fn part_two(donut: &Input) -> Result<String, Error> {
+    Ok(donut.shortest_recursive()?.to_string())
+}
+
+/// A donut-shaped maze whose two-letter labels mark portals.
+///
+/// Portals with the same label are linked, one on the outer edge of the
+/// donut and one on the inner edge. Only the passages between portals
+/// matter, so the maze is reduced to a graph of portal to portal distances.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Donut {
+    portals: Vec<Portal>,
+    /// The other end of each portal, if it has one.
+    links: Vec<Option<usize>>,
+    /// `(portal, distance)` for every portal reachable on foot from each.
+    edges: Vec<Vec<(usize, usize)>>,
+    start: usize,
+    end: usize,
+    max_depth: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Portal {
+    label: [u8; 2],
+    /// The open cell next to the label.
+    point: Point,
+    outer: bool,
+}
+
+impl Donut {
+    pub fn new(maze: &Maze) -> Result<Self, Error> {
+        let portals = find_portals(maze)?;
+
+        let mut by_label = HashMap::<_, Vec<usize>>::new();
+        for (i, portal) in portals.iter().enumerate() {
+            by_label.entry(portal.label).or_default().push(i);
+        }
+
+        let mut links = vec![None; portals.len()];
+        for (label, ids) in &by_label {
+            let expected = if *label == START || *label == END {
+                1
+            } else {
+                2
+            };
+            if ids.len() != expected {
+                bail!(
+                    "Expected {} portal(s) labelled {}, but found {}.",
+                    expected,
+                    String::from_utf8_lossy(label),
+                    ids.len()
+                );
+            }
+            if let [a, b] = ids[..] {
+                if portals[a].outer == portals[b].outer {
+                    bail!(
+                        "Portals labelled {} are both on the same edge.",
+                        String::from_utf8_lossy(label)
+                    );
+                }
+                links[a] = Some(b);
+                links[b] = Some(a);
+            }
+        }
+
+        let start = match by_label.get(&START) {
+            Some(ids) => ids[0],
+            None => bail!("Maze has no start portal."),
+        };
+        let end = match by_label.get(&END) {
+            Some(ids) => ids[0],
+            None => bail!("Maze has no end portal."),
+        };
+
+        let ids = portals
+            .iter()
+            .enumerate()
+            .map(|(i, portal)| (portal.point, i))
+            .collect::<HashMap<_, _>>();
+        let edges = portals
+            .iter()
+            .map(|portal| {
+                maze.bfs(portal.point, (), |_, cell, _| match cell {
+                    OPEN => Some(()),
+                    _ => None,
+                })
+                .into_iter()
+                .filter(|&(_, dist, _)| dist > 0)
+                .filter_map(|(point, dist, _)| Some((*ids.get(&point)?, dist)))
+                .collect()
+            })
+            .collect();
+
+        Ok(Self {
+            portals,
+            links,
+            edges,
+            start,
+            end,
+            max_depth: MAX_DEPTH,
+        })
+    }
+
+    /// Sets the deepest level the recursive search may reach.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Returns the fewest steps from `AA` to `ZZ`, where stepping through a
+    /// portal takes one step.
+    pub fn shortest(&self) -> Result<usize, Error> {
+        self.search(false)
+            .ok_or_else(|| error!("Unable to find a path from AA to ZZ."))
+    }
+
+    /// Returns the fewest steps from `AA` to `ZZ` when the maze is recursive:
+    /// inner portals lead one level deeper and outer portals one level back
+    /// up. Outer portals are walls at the outermost level, and `AA` and `ZZ`
+    /// are walls at every other level.
+    pub fn shortest_recursive(&self) -> Result<usize, Error> {
+        self.search(true).ok_or_else(|| {
+            error!(
+                "Unable to find a path from AA to ZZ within {} levels.",
+                self.max_depth
+            )
+        })
+    }
+
+    /// Dijkstra's algorithm over `(portal, level)`. The level stays at zero
+    /// unless `recursive` is set.
+    fn search(&self, recursive: bool) -> Option<usize> {
+        let mut best = HashMap::new();
+        let mut heap = BinaryHeap::new();
+        best.insert((self.start, 0), 0);
+        heap.push(Reverse((0, self.start, 0usize)));
+
+        while let Some(Reverse((dist, portal, level))) = heap.pop() {
+            if portal == self.end && level == 0 {
+                return Some(dist);
+            }
+            if best
+                .get(&(portal, level))
+                .is_some_and(|&other| other < dist)
+            {
+                continue;
+            }
+
+            let walks = self.edges[portal].iter().copied();
+            let jump = self.links[portal].and_then(|other| {
+                if !recursive {
+                    return Some((other, level, 1));
+                }
+                if self.portals[portal].outer {
+                    Some((other, level.checked_sub(1)?, 1))
+                } else if level < self.max_depth {
+                    Some((other, level + 1, 1))
+                } else {
+                    None
+                }
+            });
+
+            for (next, level, step) in walks.map(|(next, step)| (next, level, step)).chain(jump) {
+                let dist = dist + step;
+                let shorter = match best.get(&(next, level)) {
+                    Some(&other) => dist < other,
+                    None => true,
+                };
+                if shorter {
+                    best.insert((next, level), dist);
+                    heap.push(Reverse((dist, next, level)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Finds every open cell next to a label, reading each label left to right
+/// or top to bottom.
+fn find_portals(maze: &Maze) -> Result<Vec<Portal>, Error> {
+    let mut portals = Vec::new();
+
+    for (point, cell) in maze.cells() {
+        if cell != OPEN {
+            continue;
+        }
+
+        for near in maze.neighbors(point) {
+            if !maze.get(near).unwrap().is_ascii_uppercase() {
+                continue;
+            }
+
+            // The label continues in the same direction, away from the cell.
+            let far = (
+                (2 * near.0).wrapping_sub(point.0),
+                (2 * near.1).wrapping_sub(point.1),
+            );
+            let far_cell = match maze.get(far) {
+                Some(c) if c.is_ascii_uppercase() => c,
+                _ => bail!("Label next to {:?} is not two letters long.", point),
+            };
+            let near_cell = maze.get(near).unwrap();
+            let label = if far < near {
+                [far_cell, near_cell]
+            } else {
+                [near_cell, far_cell]
+            };
+
+            let (x, y) = point;
+            let outer = x == 2 || y == 2 || x + 3 == maze.cols() || y + 3 == maze.rows();
+
+            portals.push(Portal {
+                label,
+                point,
+                outer,
+            });
+        }
+    }
+
+    Ok(portals)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    #[ignore]
-    fn test_dayxx() {}
+    fn test_day20() {
+        let input = [
+            "         A           ",
+            "         A           ",
+            "  #######.#########  ",
+            "  #######.........#  ",
+            "  #######.#######.#  ",
+            "  #######.#######.#  ",
+            "  #######.#######.#  ",
+            "  #####  B    ###.#  ",
+            "BC...##  C    ###.#  ",
+            "  ##.##       ###.#  ",
+            "  ##...DE  F  ###.#  ",
+            "  #####    G  ###.#  ",
+            "  #########.#####.#  ",
+            "DE..#######...###.#  ",
+            "  #.#########.###.#  ",
+            "FG..#########.....#  ",
+            "  ###########.#####  ",
+            "             Z       ",
+            "             Z       ",
+        ]
+        .join("\n");
+        let donut = Donut::new(&input.parse().unwrap()).unwrap();
+        assert_eq!(donut.shortest().unwrap(), 23);
+        assert_eq!(donut.shortest_recursive().unwrap(), 26);
+
+        let input = [
+            "             Z L X W       C                 ",
+            "             Z P Q B       K                 ",
+            "  ###########.#.#.#.#######.###############  ",
+            "  #...#.......#.#.......#.#.......#.#.#...#  ",
+            "  ###.#.#.#.#.#.#.#.###.#.#.#######.#.#.###  ",
+            "  #.#...#.#.#...#.#.#...#...#...#.#.......#  ",
+            "  #.###.#######.###.###.#.###.###.#.#######  ",
+            "  #...#.......#.#...#...#.............#...#  ",
+            "  #.#########.#######.#.#######.#######.###  ",
+            "  #...#.#    F       R I       Z    #.#.#.#  ",
+            "  #.###.#    D       E C       H    #.#.#.#  ",
+            "  #.#...#                           #...#.#  ",
+            "  #.###.#                           #.###.#  ",
+            "  #.#....OA                       WB..#.#..ZH",
+            "  #.###.#                           #.#.#.#  ",
+            "CJ......#                           #.....#  ",
+            "  #######                           #######  ",
+            "  #.#....CK                         #......IC",
+            "  #.###.#                           #.###.#  ",
+            "  #.....#                           #...#.#  ",
+            "  ###.###                           #.#.#.#  ",
+            "XF....#.#                         RF..#.#.#  ",
+            "  #####.#                           #######  ",
+            "  #......CJ                       NM..#...#  ",
+            "  ###.#.#                           #.###.#  ",
+            "RE....#.#                           #......RF",
+            "  ###.###        X   X       L      #.#.#.#  ",
+            "  #.....#        F   Q       P      #.#.#.#  ",
+            "  ###.###########.###.#######.#########.###  ",
+            "  #.....#...#.....#.......#...#.....#.#...#  ",
+            "  #####.#.###.#######.#######.###.###.#.#.#  ",
+            "  #.......#.......#.#.#.#.#...#...#...#.#.#  ",
+            "  #####.###.#####.#.#.#.#.###.###.#.###.###  ",
+            "  #.......#.....#.#...#...............#...#  ",
+            "  #############.#.#.###.###################  ",
+            "               A O F   N                     ",
+            "               A A D   M                     ",
+        ]
+        .join("\n");
+        let donut = Donut::new(&input.parse().unwrap()).unwrap();
+        assert_eq!(donut.shortest().unwrap(), 77);
+        assert_eq!(donut.shortest_recursive().unwrap(), 396);
+        assert!(donut.with_max_depth(1).shortest_recursive().is_err());
+
+        let maze = "  A  \n  A  \n##.##\n##.##\n  Z  \n  Y  ".parse().unwrap();
+        assert!(Donut::new(&maze).is_err());
+
+        crate::utils::tests::test_full_problem(20, run, "644", "7798");
+    }
 }
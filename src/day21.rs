@@ -1,15 +1,657 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::computer::{ComputerST, Queue, State};
 use crate::error::Error;
+use crate::rom::Rom;
+
+/// Most instructions the springdroid's memory can hold.
+const MAX_INSTRUCTIONS: usize = 15;
+/// How far a jump carries the springdroid.
+const JUMP: usize = 4;
+
+/// Jump if there is a hole in the next three tiles and somewhere to land.
+const WALK_SCRIPT: &str = "!(A & B & C) & D";
+/// As above, but only if after landing it can either step or jump again.
+const RUN_SCRIPT: &str = "!(A & B & C) & D & (E | H)";
+
+pub type Input = Rom;
 
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    Ok((String::new(), String::new()))
+    let rom = parse(reader)?;
+
+    Ok((part_one(&rom)?, part_two(&rom)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Rom::from_reader(reader)
+}
+
+pub fn part_one(rom: &Input) -> Result<String, Error> {
+    let script = Script::compile(WALK_SCRIPT, Mode::Walk)?;
+
+    Ok(survey(rom, &script)?.to_string())
+}
+
+pub fn part_two(rom: &Input) -> Result<String, Error> {
+    let script = Script::compile(RUN_SCRIPT, Mode::Run)?;
+
+    Ok(survey(rom, &script)?.to_string())
+}
+
+/// Sends `script` to the springdroid and returns the hull damage it reports.
+///
+/// If the springdroid falls into space, the error shows the hull it fell on,
+/// which can be parsed as a `Hull` to reproduce the fall locally.
+pub fn survey<R>(rom: R, script: &Script) -> Result<i64, Error>
+where
+    R: AsRef<[i64]>,
+{
+    let mut computer = ComputerST::new(rom);
+    for b in script.to_string().bytes() {
+        computer.input_mut().enqueue(b as i64);
+    }
+
+    let mut output = String::new();
+    loop {
+        match computer.step()? {
+            State::Done => break,
+            State::NeedsInput => bail!("Springdroid asked for more input than the script."),
+            State::HasOutput => match computer.output_mut().dequeue()? {
+                val @ 0..=127 => output.push(val as u8 as char),
+                damage => return Ok(damage),
+            },
+        }
+    }
+
+    let hull = output
+        .lines()
+        .rev()
+        .find(|line| line.starts_with('#'))
+        .unwrap_or("");
+    bail!("Springdroid fell into space on the hull:\n{}", hull)
+}
+
+/// Whether the springdroid walks, seeing four tiles ahead, or runs, seeing
+/// nine.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Mode {
+    Walk,
+    Run,
+}
+
+impl Mode {
+    fn num_sensors(self) -> u8 {
+        match self {
+            Mode::Walk => 4,
+            Mode::Run => 9,
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mode::Walk => write!(f, "WALK"),
+            Mode::Run => write!(f, "RUN"),
+        }
+    }
+}
+
+/// A springscript register: a read-only sensor, `A` being the tile one
+/// ahead, or one of the writable `T` and `J`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Register {
+    Sensor(u8),
+    T,
+    J,
+}
+
+impl Register {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s.as_bytes() {
+            [b'T'] => Ok(Register::T),
+            [b'J'] => Ok(Register::J),
+            [c @ b'A'..=b'I'] => Ok(Register::Sensor(c - b'A')),
+            _ => bail!("Invalid register {:?}.", s),
+        }
+    }
+
+    fn scratch(self) -> Self {
+        match self {
+            Register::T => Register::J,
+            _ => Register::T,
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Register::Sensor(i) => write!(f, "{}", (b'A' + i) as char),
+            Register::T => write!(f, "T"),
+            Register::J => write!(f, "J"),
+        }
+    }
+}
+
+/// A springscript instruction, which stores its result in the second
+/// register.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Instruction {
+    And(Register, Register),
+    Or(Register, Register),
+    Not(Register, Register),
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::And(x, y) => write!(f, "AND {} {}", x, y),
+            Instruction::Or(x, y) => write!(f, "OR {} {}", x, y),
+            Instruction::Not(x, y) => write!(f, "NOT {} {}", x, y),
+        }
+    }
+}
+
+/// A boolean expression over the sensors, such as `!A | (!C & D)`.
+///
+/// `!` binds tighter than `&`, which binds tighter than `|`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Expr {
+    Sensor(u8),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Returns whether the expression can be computed in a single register,
+    /// with no scratch register. That holds for sensors, negations of such
+    /// expressions, and `&` or `|` where one side is a sensor or a negated
+    /// sensor and the other side qualifies.
+    fn is_linear(&self) -> bool {
+        match self {
+            Expr::Sensor(_) => true,
+            Expr::Not(e) => e.is_linear(),
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                (a.is_linear() && b.literal().is_some()) || (b.is_linear() && a.literal().is_some())
+            }
+        }
+    }
+
+    /// Returns the sensor and whether it is negated, if this is `X` or `!X`.
+    fn literal(&self) -> Option<(u8, bool)> {
+        match self {
+            Expr::Sensor(i) => Some((*i, false)),
+            Expr::Not(e) => match **e {
+                Expr::Sensor(i) => Some((i, true)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Evaluates the expression, sensor `i` reading `sensors[i]`.
+    pub fn eval(&self, sensors: &[bool]) -> bool {
+        match self {
+            Expr::Sensor(i) => sensors[*i as usize],
+            Expr::Not(e) => !e.eval(sensors),
+            Expr::And(a, b) => a.eval(sensors) && b.eval(sensors),
+            Expr::Or(a, b) => a.eval(sensors) || b.eval(sensors),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+
+        if let Some(c) = parser.peek() {
+            bail!("Unexpected {:?} in expression {:?}.", c, s);
+        }
+
+        Ok(expr)
+    }
+}
+
+/// Recursive descent parser, one method per level of precedence.
+struct Parser {
+    tokens: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn or(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.and()?;
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.not()?;
+        while self.peek() == Some('&') {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, Error> {
+        match self.next() {
+            Some('!') => Ok(Expr::Not(Box::new(self.not()?))),
+            Some('(') => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(')') => Ok(expr),
+                    _ => bail!("Expected `)` in expression."),
+                }
+            }
+            Some(c @ 'A'..='I') => Ok(Expr::Sensor(c as u8 - b'A')),
+            Some(c) => bail!("Unexpected {:?} in expression.", c),
+            None => bail!("Unexpected end of expression."),
+        }
+    }
+}
+
+/// A springscript program: instructions that leave `J` set when the
+/// springdroid should jump, and whether it walks or runs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Script {
+    instructions: Vec<Instruction>,
+    mode: Mode,
+}
+
+impl Script {
+    /// Builds a script from instructions, checking that it fits in memory,
+    /// only writes to `T` and `J`, and only reads sensors `mode` has.
+    pub fn new(instructions: Vec<Instruction>, mode: Mode) -> Result<Self, Error> {
+        if instructions.len() > MAX_INSTRUCTIONS {
+            bail!(
+                "Script has {} instructions, but the limit is {}.",
+                instructions.len(),
+                MAX_INSTRUCTIONS
+            );
+        }
+
+        for instruction in &instructions {
+            let (x, y) = match *instruction {
+                Instruction::And(x, y) | Instruction::Or(x, y) | Instruction::Not(x, y) => (x, y),
+            };
+            if let Register::Sensor(_) = y {
+                bail!("`{}` writes to a read-only register.", instruction);
+            }
+            if let Register::Sensor(i) = x {
+                if i >= mode.num_sensors() {
+                    bail!(
+                        "`{}` reads a sensor not available when {}ing.",
+                        instruction,
+                        mode
+                    );
+                }
+            }
+        }
+
+        Ok(Self { instructions, mode })
+    }
+
+    /// Compiles a boolean expression such as `!A | (!C & D)` into a script
+    /// that jumps when it is true.
+    ///
+    /// With only `T` and `J` to work in, at each `&` or `|` at least one side
+    /// must be computable without a scratch register, such as a chain of
+    /// sensors like `A & !B | C`.
+    pub fn compile(expr: &str, mode: Mode) -> Result<Self, Error> {
+        let expr = expr.parse::<Expr>()?;
+        let mut compiler = Compiler::default();
+        compiler.compile(&expr, Register::J)?;
+
+        Self::new(compiler.finish(), mode)
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Runs the script with the given sensor readings, returning whether the
+    /// springdroid jumps.
+    pub fn jumps(&self, sensors: &[bool]) -> bool {
+        let (mut t, mut j) = (false, false);
+
+        for instruction in &self.instructions {
+            let read = |r: Register, t: bool, j: bool| match r {
+                Register::Sensor(i) => sensors.get(i as usize).copied().unwrap_or(true),
+                Register::T => t,
+                Register::J => j,
+            };
+            let (y, val) = match *instruction {
+                Instruction::And(x, y) => (y, read(x, t, j) && read(y, t, j)),
+                Instruction::Or(x, y) => (y, read(x, t, j) || read(y, t, j)),
+                Instruction::Not(x, y) => (y, !read(x, t, j)),
+            };
+            match y {
+                Register::T => t = val,
+                _ => j = val,
+            }
+        }
+
+        j
+    }
+}
+
+impl FromStr for Script {
+    type Err = Error;
+
+    /// Parses springscript as it is sent to the springdroid: one instruction
+    /// per line, then `WALK` or `RUN`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut instructions = Vec::new();
+
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            let instruction = match words[..] {
+                ["WALK"] => return Self::new(instructions, Mode::Walk),
+                ["RUN"] => return Self::new(instructions, Mode::Run),
+                [op, x, y] => {
+                    let (x, y) = (Register::parse(x)?, Register::parse(y)?);
+                    match op {
+                        "AND" => Instruction::And(x, y),
+                        "OR" => Instruction::Or(x, y),
+                        "NOT" => Instruction::Not(x, y),
+                        _ => bail!("Unknown instruction {:?}.", op),
+                    }
+                }
+                _ => bail!("Invalid springscript line {:?}.", line),
+            };
+            instructions.push(instruction);
+        }
+
+        bail!("Springscript must end with WALK or RUN.")
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{}", instruction)?;
+        }
+        writeln!(f, "{}", self.mode)
+    }
+}
+
+/// Emits instructions for an `Expr`, tracking which registers still hold
+/// their initial `false`.
+#[derive(Debug)]
+struct Compiler {
+    instructions: Vec<Instruction>,
+    /// Whether `T` and `J` have not been written yet.
+    fresh: [bool; 2],
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self {
+            instructions: Vec::new(),
+            fresh: [true, true],
+        }
+    }
+}
+
+impl Compiler {
+    fn emit(&mut self, instruction: Instruction) {
+        let y = match instruction {
+            Instruction::And(_, y) | Instruction::Or(_, y) | Instruction::Not(_, y) => y,
+        };
+        self.fresh[(y == Register::J) as usize] = false;
+
+        // Two negations in a row cancel out.
+        if let Instruction::Not(x, y) = instruction {
+            if x == y && self.instructions.last() == Some(&instruction) {
+                self.instructions.pop();
+                return;
+            }
+        }
+
+        self.instructions.push(instruction);
+    }
+
+    fn is_fresh(&self, r: Register) -> bool {
+        self.fresh[(r == Register::J) as usize]
+    }
+
+    /// Leaves the value of `expr` in `r`, using the other writable register
+    /// as scratch only if `expr` is not linear.
+    fn compile(&mut self, expr: &Expr, r: Register) -> Result<(), Error> {
+        match expr {
+            Expr::Sensor(i) => {
+                let x = Register::Sensor(*i);
+                if self.is_fresh(r) {
+                    self.emit(Instruction::Or(x, r));
+                } else {
+                    self.emit(Instruction::Not(x, r));
+                    self.emit(Instruction::Not(r, r));
+                }
+            }
+            Expr::Not(e) => match **e {
+                Expr::Sensor(i) => self.emit(Instruction::Not(Register::Sensor(i), r)),
+                _ => {
+                    self.compile(e, r)?;
+                    self.emit(Instruction::Not(r, r));
+                }
+            },
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                let is_and = matches!(expr, Expr::And(..));
+
+                // Put the side that is a literal, or failing that the side
+                // that needs no scratch, on the right.
+                let swap = (b.literal().is_none() && a.literal().is_some())
+                    || (!b.is_linear() && a.is_linear());
+                let (a, b) = if swap { (b, a) } else { (a, b) };
+
+                self.compile(a, r)?;
+
+                match b.literal() {
+                    Some((i, false)) => {
+                        let x = Register::Sensor(i);
+                        self.emit(if is_and {
+                            Instruction::And(x, r)
+                        } else {
+                            Instruction::Or(x, r)
+                        });
+                    }
+                    // By De Morgan, r & !x is !(!r | x) and r | !x is
+                    // !(!r & x).
+                    Some((i, true)) => {
+                        let x = Register::Sensor(i);
+                        self.emit(Instruction::Not(r, r));
+                        self.emit(if is_and {
+                            Instruction::Or(x, r)
+                        } else {
+                            Instruction::And(x, r)
+                        });
+                        self.emit(Instruction::Not(r, r));
+                    }
+                    None if b.is_linear() => {
+                        let s = r.scratch();
+                        self.compile(b, s)?;
+                        self.emit(if is_and {
+                            Instruction::And(s, r)
+                        } else {
+                            Instruction::Or(s, r)
+                        });
+                    }
+                    None => bail!("Expression is too complex to compute in two registers."),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Vec<Instruction> {
+        self.instructions
+    }
+}
+
+/// A stretch of hull for simulating scripts locally: `#` is hull and `.` is
+/// a hole, starting from the springdroid's tile.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Hull(Vec<bool>);
+
+impl Hull {
+    /// Walks the springdroid along the hull, returning an error with the
+    /// tile it falls into, if it does.
+    pub fn simulate(&self, script: &Script) -> Result<(), Error> {
+        let sensors = script.mode().num_sensors() as usize;
+        let mut pos = 0;
+
+        while pos < self.0.len() {
+            if !self.0[pos] {
+                bail!(
+                    "Springdroid fell into the hole at tile {} of {}.",
+                    pos,
+                    self
+                );
+            }
+
+            // Past the end of the hull counts as solid ground.
+            let view = (1..=sensors)
+                .map(|i| self.0.get(pos + i).copied().unwrap_or(true))
+                .collect::<Vec<_>>();
+            pos += if script.jumps(&view) { JUMP } else { 1 };
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Hull {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hull = s
+            .trim()
+            .chars()
+            .map(|c| match c {
+                '#' | '@' => Ok(true),
+                '.' => Ok(false),
+                _ => bail!("Invalid hull tile {:?}.", c),
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        if hull.first() != Some(&true) {
+            bail!("Springdroid must start on the hull.");
+        }
+
+        Ok(Hull(hull))
+    }
+}
+
+impl fmt::Display for Hull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &tile in &self.0 {
+            write!(f, "{}", if tile { '#' } else { '.' })?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    #[ignore]
-    fn test_dayxx() {}
+    fn test_day21() {
+        let test_cases = &[
+            // expression, springscript
+            ("A", "OR A J\nWALK\n"),
+            (
+                "!A | (!C & D)",
+                "NOT C J\nAND D J\nNOT J J\nAND A J\nNOT J J\nWALK\n",
+            ),
+            (
+                "!(A & B & C) & D",
+                "OR A J\nAND B J\nAND C J\nNOT J J\nAND D J\nWALK\n",
+            ),
+            ("!!(A & !B)", "OR A J\nNOT J J\nOR B J\nNOT J J\nWALK\n"),
+        ];
+        for (expr, expected) in test_cases {
+            let script = Script::compile(expr, Mode::Walk).unwrap();
+            assert_eq!(script.to_string(), *expected);
+            assert_eq!(expected.parse::<Script>().unwrap(), script);
+        }
+
+        // Compiled scripts agree with the expressions they came from for
+        // every combination of sensors.
+        for expr in &[
+            WALK_SCRIPT,
+            RUN_SCRIPT,
+            "!A | (!C & D)",
+            "(A | !B) & !(C | D) & !E",
+            "(A | B) & (C | D)",
+        ] {
+            let script = Script::compile(expr, Mode::Run).unwrap();
+            let expr = expr.parse::<Expr>().unwrap();
+            for bits in 0..(1 << 9) {
+                let sensors = (0..9).map(|i| bits & (1 << i) != 0).collect::<Vec<_>>();
+                assert_eq!(script.jumps(&sensors), expr.eval(&sensors), "{}", script);
+            }
+        }
+
+        let walking = Script::compile(WALK_SCRIPT, Mode::Walk).unwrap();
+        let running = Script::compile(RUN_SCRIPT, Mode::Run).unwrap();
+        for hull in &[
+            "#####.###########",
+            "#####...#########",
+            "#####..#.########",
+        ] {
+            let hull = hull.parse::<Hull>().unwrap();
+            assert!(hull.simulate(&walking).is_ok(), "{}", hull);
+            assert!(hull.simulate(&running).is_ok(), "{}", hull);
+        }
+        let hull = "#####.#.##.#.####".parse::<Hull>().unwrap();
+        assert!(hull.simulate(&walking).is_err());
+        assert!(hull.simulate(&running).is_ok());
+
+        assert!(Script::compile("E", Mode::Walk).is_err());
+        assert!(Script::compile("(A | B) & (C | D) | (E | F) & (G | H)", Mode::Run).is_err());
+        assert!(Script::compile("A & (B", Mode::Walk).is_err());
+        assert!(Script::compile("A B", Mode::Walk).is_err());
+        assert!("NOT A B\nWALK\n".parse::<Script>().is_err());
+        assert!("NOT A J\n".parse::<Script>().is_err());
+        assert!(Script::new(
+            vec![Instruction::Not(Register::J, Register::J); 16],
+            Mode::Walk
+        )
+        .is_err());
+
+        crate::utils::tests::test_full_problem(21, run, "19355790", "1140920822");
+    }
 }
@@ -1,15 +1,255 @@
+use std::fmt;
+use std::str::FromStr;
+
 use crate::error::Error;
+use crate::utils::math::{inv_mod, mul_mod};
+
+const SMALL_DECK: u64 = 10_007;
+const SMALL_CARD: u64 = 2019;
+const LARGE_DECK: u64 = 119_315_717_514_047;
+const LARGE_SHUFFLES: u64 = 101_741_582_076_661;
+const LARGE_POSITION: u64 = 2020;
+
+pub type Input = Vec<Technique>;
 
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    Ok((String::new(), String::new()))
+    let techniques = parse(reader)?;
+
+    Ok((part_one(&techniques)?, part_two(&techniques)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    let mut techniques = Vec::new();
+
+    for (i, res) in reader.lines().enumerate() {
+        let line = res?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let technique = line
+            .parse::<Technique>()
+            .map_err(|e| error!("{} (line {})", e, i + 1))?;
+        techniques.push(technique);
+    }
+
+    Ok(techniques)
+}
+
+pub fn part_one(techniques: &Input) -> Result<String, Error> {
+    let shuffle = Shuffle::new(techniques, SMALL_DECK)?;
+
+    Ok(shuffle.apply(SMALL_CARD).to_string())
+}
+
+pub fn part_two(techniques: &Input) -> Result<String, Error> {
+    let shuffle = Shuffle::new(techniques, LARGE_DECK)?.pow(LARGE_SHUFFLES);
+
+    // The card that ends up at the position is the one the inverse shuffle
+    // sends there.
+    Ok(shuffle.inverse()?.apply(LARGE_POSITION).to_string())
+}
+
+/// One of the space cards' shuffling techniques.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Technique {
+    NewStack,
+    Cut(i64),
+    Increment(u64),
+}
+
+impl FromStr for Technique {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "deal into new stack" {
+            Ok(Technique::NewStack)
+        } else if let Some(n) = s.strip_prefix("cut ") {
+            Ok(Technique::Cut(n.parse()?))
+        } else if let Some(n) = s.strip_prefix("deal with increment ") {
+            Ok(Technique::Increment(n.parse()?))
+        } else {
+            bail!("Unknown shuffling technique {:?}.", s)
+        }
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Technique::NewStack => write!(f, "deal into new stack"),
+            Technique::Cut(n) => write!(f, "cut {}", n),
+            Technique::Increment(n) => write!(f, "deal with increment {}", n),
+        }
+    }
+}
+
+/// A shuffle of a deck of `size` cards, as the map sending the card at
+/// position `x` to position `a * x + b`, modulo `size`.
+///
+/// Every technique is such a map, and so is any sequence of them, so a whole
+/// shuffle can be composed, repeated and reversed without touching a deck.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Shuffle {
+    a: u64,
+    b: u64,
+    size: u64,
+}
+
+impl Shuffle {
+    /// Leaves every card where it is.
+    pub fn identity(size: u64) -> Self {
+        Self { a: 1, b: 0, size }
+    }
+
+    /// Composes `techniques`, applied in order, for a deck of `size` cards.
+    pub fn new(techniques: &[Technique], size: u64) -> Result<Self, Error> {
+        techniques
+            .iter()
+            .try_fold(Self::identity(size), |shuffle, &technique| {
+                Ok(shuffle.then(&Self::technique(technique, size)?))
+            })
+    }
+
+    /// The map for a single technique.
+    pub fn technique(technique: Technique, size: u64) -> Result<Self, Error> {
+        if size == 0 {
+            bail!("Deck must have at least one card.");
+        }
+
+        let (a, b) = match technique {
+            // x -> -x - 1
+            Technique::NewStack => (size - 1, size - 1),
+            // x -> x - n
+            Technique::Cut(n) => (1, (-(n as i128)).rem_euclid(size as i128) as u64),
+            // x -> n * x, which only shuffles if n has an inverse.
+            Technique::Increment(n) => {
+                inv_mod(n, size)
+                    .map_err(|_| error!("Increment {} does not shuffle {} cards.", n, size))?;
+                (n % size, 0)
+            }
+        };
+
+        Ok(Self { a, b, size })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns where the card at `position` ends up.
+    pub fn apply(&self, position: u64) -> u64 {
+        (mul_mod(self.a, position % self.size, self.size) + self.b) % self.size
+    }
+
+    /// This shuffle followed by `other`.
+    ///
+    /// # Panics
+    ///
+    /// If the two are for different sizes of deck.
+    pub fn then(&self, other: &Self) -> Self {
+        assert_eq!(self.size, other.size, "Shuffles are for different decks.");
+
+        // other(self(x)) = a2 * (a1 * x + b1) + b2
+        Self {
+            a: mul_mod(other.a, self.a, self.size),
+            b: (mul_mod(other.a, self.b, self.size) + other.b) % self.size,
+            size: self.size,
+        }
+    }
+
+    /// This shuffle repeated `n` times, by repeated squaring.
+    pub fn pow(&self, mut n: u64) -> Self {
+        let mut ans = Self::identity(self.size);
+        let mut square = *self;
+
+        while n > 0 {
+            if n & 1 == 1 {
+                ans = ans.then(&square);
+            }
+            square = square.then(&square);
+            n >>= 1;
+        }
+
+        ans
+    }
+
+    /// The shuffle that puts every card back.
+    pub fn inverse(&self) -> Result<Self, Error> {
+        // x = a^-1 * (y - b)
+        let a = inv_mod(self.a, self.size)?;
+        let b = mul_mod(a, (self.size - self.b) % self.size, self.size);
+
+        Ok(Self {
+            a,
+            b,
+            size: self.size,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    #[ignore]
-    fn test_dayxx() {}
+    fn test_day22() {
+        let test_cases = &[
+            // input, expected deck
+            (
+                "deal with increment 7\ndeal into new stack\ndeal into new stack",
+                [0, 3, 6, 9, 2, 5, 8, 1, 4, 7],
+            ),
+            (
+                "cut 6\ndeal with increment 7\ndeal into new stack",
+                [3, 0, 7, 4, 1, 8, 5, 2, 9, 6],
+            ),
+            (
+                "deal with increment 7\ndeal with increment 9\ncut -2",
+                [6, 3, 0, 7, 4, 1, 8, 5, 2, 9],
+            ),
+            (
+                "deal into new stack\ncut -2\ndeal with increment 7\ncut 8\ncut -4\ndeal with increment 7\ncut 3\ndeal with increment 9\ndeal with increment 3\ncut -1",
+                [9, 2, 5, 8, 1, 4, 7, 0, 3, 6],
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            let techniques = parse(input.as_bytes()).unwrap();
+            let shuffle = Shuffle::new(&techniques, 10).unwrap();
+            let inverse = shuffle.inverse().unwrap();
+
+            for card in 0..10 {
+                assert_eq!(expected[shuffle.apply(card) as usize], card);
+                assert_eq!(inverse.apply(shuffle.apply(card)), card);
+            }
+
+            let mut repeated = Shuffle::identity(10);
+            for n in 0..20 {
+                assert_eq!(shuffle.pow(n), repeated);
+                repeated = repeated.then(&shuffle);
+            }
+
+            let output = techniques
+                .iter()
+                .map(Technique::to_string)
+                .collect::<Vec<_>>()
+                .join("\n");
+            assert_eq!(output, *input);
+        }
+
+        assert!(Shuffle::technique(Technique::Increment(4), 10).is_err());
+        assert!(parse("deal with increment x".as_bytes()).is_err());
+        assert!(parse("shuffle".as_bytes()).is_err());
+
+        crate::utils::tests::test_full_problem(22, run, "8326", "43781998578719");
+    }
 }
@@ -1,15 +1,235 @@
+use std::collections::VecDeque;
+
+use crate::computer::{ComputerST, Queue, State};
 use crate::error::Error;
+use crate::rom::Rom;
+
+const NUM_NICS: usize = 50;
+const NAT: i64 = 255;
+/// Rounds to run before assuming the network will never produce an answer.
+const MAX_ROUNDS: usize = 100_000;
+
+pub type Input = Rom;
 
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    Ok((String::new(), String::new()))
+    let rom = parse(reader)?;
+
+    Ok((part_one(&rom)?, part_two(&rom)?))
+}
+
+pub fn parse<R>(reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    Rom::from_reader(reader)
+}
+
+pub fn part_one(rom: &Input) -> Result<String, Error> {
+    let mut network = Network::new(rom, NUM_NICS)?;
+    let packet = network.first_nat_packet()?;
+
+    Ok(packet.y.to_string())
+}
+
+pub fn part_two(rom: &Input) -> Result<String, Error> {
+    let mut network = Network::new(rom, NUM_NICS)?;
+    let y = network.first_repeated_wake_up()?;
+
+    Ok(y.to_string())
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Packet {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// A network interface controller: a computer, the packets waiting for it,
+/// and whatever part of a packet it has sent so far.
+#[derive(Clone, Debug)]
+struct Nic {
+    computer: ComputerST,
+    inbox: VecDeque<Packet>,
+    outbox: Vec<i64>,
+}
+
+/// A network of NICs, each running the same program, plus the NAT that
+/// monitors it.
+///
+/// Everything runs on one thread in rounds. Each round, every NIC in turn is
+/// handed its next packet, or `-1` if it has none, and runs until it asks for
+/// more input, and the packets it sends are delivered straight away. This
+/// makes runs deterministic, and the network is idle exactly when a round
+/// passes in which no NIC had a packet waiting and none sent one.
+#[derive(Clone, Debug)]
+pub struct Network {
+    nics: Vec<Nic>,
+    /// Every packet sent to the NAT, in order.
+    nat: Vec<Packet>,
+    rounds: usize,
+}
+
+impl Network {
+    /// Boots `size` NICs running `rom`, giving each its address.
+    pub fn new<R>(rom: R, size: usize) -> Result<Self, Error>
+    where
+        R: AsRef<[i64]>,
+    {
+        let mut network = Self {
+            nics: Vec::with_capacity(size),
+            nat: Vec::new(),
+            rounds: 0,
+        };
+
+        let mut sent = Vec::new();
+        for address in 0..size {
+            let mut computer = ComputerST::new(rom.as_ref());
+            computer.input_mut().enqueue(address as i64);
+            network.nics.push(Nic {
+                computer,
+                inbox: VecDeque::new(),
+                outbox: Vec::new(),
+            });
+            network.resume(address, &mut sent)?;
+        }
+        for (dest, packet) in sent {
+            network.deliver(dest, packet)?;
+        }
+
+        Ok(network)
+    }
+
+    /// Runs every NIC once, returning whether the network was idle.
+    pub fn round(&mut self) -> Result<bool, Error> {
+        self.rounds += 1;
+        if self.rounds > MAX_ROUNDS {
+            bail!("Network is still running after {} rounds.", MAX_ROUNDS);
+        }
+
+        let mut idle = true;
+        let mut sent = Vec::new();
+
+        for address in 0..self.nics.len() {
+            let nic = &mut self.nics[address];
+            match nic.inbox.pop_front() {
+                Some(packet) => {
+                    idle = false;
+                    nic.computer.input_mut().enqueue(packet.x);
+                    nic.computer.input_mut().enqueue(packet.y);
+                }
+                None => nic.computer.input_mut().enqueue(-1),
+            }
+
+            self.resume(address, &mut sent)?;
+            if !sent.is_empty() {
+                idle = false;
+            }
+            for (dest, packet) in sent.drain(..) {
+                self.deliver(dest, packet)?;
+            }
+        }
+
+        Ok(idle)
+    }
+
+    /// Every packet the NAT has received so far.
+    pub fn nat_packets(&self) -> &[Packet] {
+        &self.nat
+    }
+
+    /// Runs until a packet is sent to the NAT, and returns it.
+    pub fn first_nat_packet(&mut self) -> Result<Packet, Error> {
+        while self.nat.is_empty() {
+            self.round()?;
+        }
+
+        Ok(self.nat[0])
+    }
+
+    /// Runs the network, with the NAT sending the last packet it received to
+    /// address 0 whenever the network is idle, and returns the first `y` it
+    /// sends twice in a row.
+    pub fn first_repeated_wake_up(&mut self) -> Result<i64, Error> {
+        let mut last = None;
+
+        loop {
+            if !self.round()? {
+                continue;
+            }
+
+            let packet = match self.nat.last() {
+                Some(&packet) => packet,
+                None => bail!("Network is idle, but the NAT has no packet to wake it."),
+            };
+            if last == Some(packet.y) {
+                return Ok(packet.y);
+            }
+            last = Some(packet.y);
+            self.deliver(0, packet)?;
+        }
+    }
+
+    /// Runs the NIC at `address` until it needs input, collecting the
+    /// packets it sends.
+    fn resume(&mut self, address: usize, sent: &mut Vec<(i64, Packet)>) -> Result<(), Error> {
+        let nic = &mut self.nics[address];
+
+        loop {
+            match nic.computer.step()? {
+                State::NeedsInput => return Ok(()),
+                State::Done => bail!("NIC {} halted.", address),
+                State::HasOutput => {
+                    nic.outbox.push(nic.computer.output_mut().dequeue()?);
+                    if let [dest, x, y] = nic.outbox[..] {
+                        sent.push((dest, Packet { x, y }));
+                        nic.outbox.clear();
+                    }
+                }
+            }
+        }
+    }
+
+    fn deliver(&mut self, dest: i64, packet: Packet) -> Result<(), Error> {
+        if dest == NAT {
+            self.nat.push(packet);
+            return Ok(());
+        }
+
+        match self.nics.get_mut(dest as usize) {
+            Some(nic) if dest >= 0 => nic.inbox.push_back(packet),
+            _ => bail!("Packet sent to unknown address {}.", dest),
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    #[ignore]
-    fn test_dayxx() {}
+    fn test_day23() {
+        // Each NIC sends its address to the NAT, then reads forever.
+        let rom = "3,100,104,255,4,100,4,100,3,101,1105,1,8"
+            .parse::<Rom>()
+            .unwrap();
+        let mut network = Network::new(&rom, 3).unwrap();
+        assert_eq!(network.first_nat_packet().unwrap(), Packet { x: 0, y: 0 });
+        assert_eq!(network.nat_packets().len(), 3);
+        assert_eq!(network.first_repeated_wake_up().unwrap(), 2);
+
+        let rom = "3,100,104,77,4,100,4,100,3,101,1105,1,8"
+            .parse::<Rom>()
+            .unwrap();
+        assert!(Network::new(&rom, 3).is_err());
+
+        let rom = "3,100,99".parse::<Rom>().unwrap();
+        assert!(Network::new(&rom, 3).is_err());
+
+        crate::utils::tests::test_full_problem(23, run, "20367", "15080");
+    }
 }
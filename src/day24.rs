@@ -1,15 +1,369 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
 use crate::error::Error;
 
+const SIZE: usize = 5;
+const TILES: usize = SIZE * SIZE;
+const CENTER: usize = TILES / 2;
+const MINUTES: usize = 200;
+
+pub type Input = Grid;
+
 pub fn run<R>(reader: R) -> Result<(String, String), Error>
 where
     R: std::io::BufRead,
 {
-    Ok((String::new(), String::new()))
+    let grid = parse(reader)?;
+
+    Ok((part_one(&grid)?, part_two(&grid)?))
+}
+
+pub fn parse<R>(mut reader: R) -> Result<Input, Error>
+where
+    R: std::io::BufRead,
+{
+    let mut buf = String::new();
+    reader.read_to_string(&mut buf)?;
+    buf.parse()
+}
+
+pub fn part_one(grid: &Input) -> Result<String, Error> {
+    Ok(grid.first_repeat().biodiversity().to_string())
+}
+
+pub fn part_two(grid: &Input) -> Result<String, Error> {
+    let mut levels = Levels::new(*grid);
+    levels.run(MINUTES);
+
+    Ok(levels.bugs().to_string())
+}
+
+/// A 5x5 grid of bugs, as a bitmask where bit `5 * y + x` is set if there is
+/// a bug at `(x, y)`.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Grid(u32);
+
+impl Grid {
+    pub fn has_bug(&self, x: usize, y: usize) -> bool {
+        x < SIZE && y < SIZE && self.0 & (1 << (y * SIZE + x)) != 0
+    }
+
+    pub fn bugs(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Each tile is worth the next power of two, reading left to right and
+    /// top to bottom, so this is just the bitmask.
+    pub fn biodiversity(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns the grid a minute later.
+    pub fn step(&self) -> Self {
+        let mut next = 0;
+
+        for (i, mask) in ADJACENT.iter().enumerate() {
+            let count = (self.0 & mask).count_ones();
+            if alive(self.0 & (1 << i) != 0, count) {
+                next |= 1 << i;
+            }
+        }
+
+        Grid(next)
+    }
+
+    /// Returns the first layout that appears twice.
+    pub fn first_repeat(&self) -> Self {
+        let mut seen = HashSet::new();
+        let mut grid = *self;
+
+        while seen.insert(grid) {
+            grid = grid.step();
+        }
+
+        grid
+    }
+
+    /// Draws the grid, marking the center with `?` if it holds a nested
+    /// level.
+    fn render(&self, recursive: bool) -> String {
+        let mut s = String::with_capacity(TILES + SIZE);
+
+        for i in 0..TILES {
+            s.push(match i {
+                CENTER if recursive => '?',
+                _ if self.0 & (1 << i) != 0 => '#',
+                _ => '.',
+            });
+            if i % SIZE == SIZE - 1 {
+                s.push('\n');
+            }
+        }
+
+        s
+    }
+}
+
+impl FromStr for Grid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+
+        let mut mask = 0;
+        let mut rows = 0;
+        for (y, line) in lines.enumerate() {
+            if y >= SIZE || line.len() != SIZE {
+                bail!("Grid must be {} by {} tiles.", SIZE, SIZE);
+            }
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    '#' => mask |= 1 << (y * SIZE + x),
+                    '.' | '?' => (),
+                    _ => bail!("Invalid tile {:?} in grid.", c),
+                }
+            }
+            rows += 1;
+        }
+
+        if rows != SIZE {
+            bail!("Grid must be {} by {} tiles.", SIZE, SIZE);
+        }
+
+        Ok(Grid(mask))
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+/// Whether a tile has a bug next minute: a bug dies unless exactly one bug
+/// is adjacent, and an empty tile becomes infested if one or two are.
+fn alive(bug: bool, neighbors: u32) -> bool {
+    neighbors == 1 || (!bug && neighbors == 2)
+}
+
+/// Bitmasks of the tiles adjacent to each tile on the same grid.
+const ADJACENT: [u32; TILES] = adjacent(false);
+
+/// As `ADJACENT`, but without the center, which holds the next level in.
+const ADJACENT_RECURSIVE: [u32; TILES] = adjacent(true);
+
+const fn adjacent(recursive: bool) -> [u32; TILES] {
+    let mut masks = [0; TILES];
+    let mut i = 0;
+
+    while i < TILES {
+        let (x, y) = (i % SIZE, i / SIZE);
+        let mut mask = 0;
+        if x > 0 {
+            mask |= 1 << (i - 1);
+        }
+        if x < SIZE - 1 {
+            mask |= 1 << (i + 1);
+        }
+        if y > 0 {
+            mask |= 1 << (i - SIZE);
+        }
+        if y < SIZE - 1 {
+            mask |= 1 << (i + SIZE);
+        }
+        if recursive {
+            mask &= !(1 << CENTER);
+        }
+        masks[i] = mask;
+        i += 1;
+    }
+
+    masks
+}
+
+/// For each tile, the tiles of the enclosing level it touches: the tile
+/// just outside whichever edges it lies on.
+const OUTER: [u32; TILES] = outer();
+
+const fn outer() -> [u32; TILES] {
+    let mut masks = [0; TILES];
+    let mut i = 0;
+
+    while i < TILES {
+        let (x, y) = (i % SIZE, i / SIZE);
+        let mut mask = 0;
+        if x == 0 {
+            mask |= 1 << (CENTER - 1);
+        }
+        if x == SIZE - 1 {
+            mask |= 1 << (CENTER + 1);
+        }
+        if y == 0 {
+            mask |= 1 << (CENTER - SIZE);
+        }
+        if y == SIZE - 1 {
+            mask |= 1 << (CENTER + SIZE);
+        }
+        masks[i] = mask;
+        i += 1;
+    }
+
+    masks
+}
+
+/// For each tile, the tiles of the nested level it touches: the whole edge
+/// facing it, for the four tiles around the center.
+const INNER: [u32; TILES] = inner();
+
+const fn inner() -> [u32; TILES] {
+    let mut masks = [0; TILES];
+    let mut k = 0;
+
+    while k < SIZE {
+        masks[CENTER - SIZE] |= 1 << k;
+        masks[CENTER + SIZE] |= 1 << (TILES - SIZE + k);
+        masks[CENTER - 1] |= 1 << (k * SIZE);
+        masks[CENTER + 1] |= 1 << (k * SIZE + SIZE - 1);
+        k += 1;
+    }
+
+    masks
+}
+
+/// Recursively folded grids, where the center of each level holds the next
+/// level in and each level sits in the center of the one outside it.
+///
+/// Only levels that have had bugs are stored; depth 0 is the starting grid,
+/// negative depths enclose it and positive depths are nested within it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Levels {
+    levels: VecDeque<u32>,
+    /// Depth of `levels[0]`.
+    min_depth: i64,
+}
+
+impl Levels {
+    pub fn new(grid: Grid) -> Self {
+        Self {
+            levels: vec![grid.0 & !(1 << CENTER)].into(),
+            min_depth: 0,
+        }
+    }
+
+    /// Returns the level at `depth`, which is empty if it has never had bugs.
+    pub fn level(&self, depth: i64) -> Grid {
+        let i = depth - self.min_depth;
+        if i < 0 {
+            return Grid::default();
+        }
+        Grid(self.levels.get(i as usize).copied().unwrap_or(0))
+    }
+
+    /// Returns the shallowest and deepest levels that have had bugs.
+    pub fn depths(&self) -> (i64, i64) {
+        (
+            self.min_depth,
+            self.min_depth + self.levels.len() as i64 - 1,
+        )
+    }
+
+    pub fn bugs(&self) -> u32 {
+        self.levels.iter().map(|level| level.count_ones()).sum()
+    }
+
+    /// Draws the level at `depth`, with `?` marking the nested level.
+    pub fn render(&self, depth: i64) -> String {
+        self.level(depth).render(true)
+    }
+
+    /// Advances every level by one minute.
+    pub fn step(&mut self) {
+        // Bugs can spread one level further out or in each minute.
+        self.levels.push_front(0);
+        self.levels.push_back(0);
+        self.min_depth -= 1;
+
+        let n = self.levels.len();
+        let next = (0..n)
+            .map(|d| {
+                let outer = if d > 0 { self.levels[d - 1] } else { 0 };
+                let inner = if d + 1 < n { self.levels[d + 1] } else { 0 };
+                let level = self.levels[d];
+
+                let mut next = 0;
+                for i in (0..TILES).filter(|&i| i != CENTER) {
+                    let count = (level & ADJACENT_RECURSIVE[i]).count_ones()
+                        + (outer & OUTER[i]).count_ones()
+                        + (inner & INNER[i]).count_ones();
+                    if alive(level & (1 << i) != 0, count) {
+                        next |= 1 << i;
+                    }
+                }
+                next
+            })
+            .collect::<VecDeque<_>>();
+        self.levels = next;
+
+        // Drop the new levels again if nothing spread to them.
+        if self.levels.len() > 1 && self.levels.front() == Some(&0) {
+            self.levels.pop_front();
+            self.min_depth += 1;
+        }
+        if self.levels.len() > 1 && self.levels.back() == Some(&0) {
+            self.levels.pop_back();
+        }
+    }
+
+    pub fn run(&mut self, minutes: usize) {
+        for _ in 0..minutes {
+            self.step();
+        }
+    }
+}
+
+impl fmt::Display for Levels {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (min, max) = self.depths();
+        for depth in min..=max {
+            if depth > min {
+                writeln!(f)?;
+            }
+            writeln!(f, "Depth {}:", depth)?;
+            write!(f, "{}", self.render(depth))?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    #[ignore]
-    fn test_dayxx() {}
+    fn test_day24() {
+        let input = "....#\n#..#.\n#..##\n..#..\n#....";
+        let grid = input.parse::<Grid>().unwrap();
+        assert_eq!(grid.to_string(), format!("{}\n", input));
+        assert_eq!(
+            grid.step().to_string(),
+            "#..#.\n####.\n###.#\n##.##\n.##..\n"
+        );
+        assert_eq!(grid.first_repeat().biodiversity(), 2129920);
+
+        let mut levels = Levels::new(grid);
+        levels.run(10);
+        assert_eq!(levels.bugs(), 99);
+        assert_eq!(levels.depths(), (-5, 5));
+        assert_eq!(levels.render(0), ".#...\n.#.##\n.#?..\n.....\n.....\n");
+        assert_eq!(levels.render(-5), "..#..\n.#.#.\n..?.#\n.#.#.\n..#..\n");
+        assert_eq!(levels.render(6), ".....\n.....\n..?..\n.....\n.....\n");
+        assert!(levels.to_string().starts_with("Depth -5:\n..#..\n"));
+
+        assert!("....#\n#..#.".parse::<Grid>().is_err());
+        assert!("....#\n#..#.\n#..##\n..#..\n#...x".parse::<Grid>().is_err());
+
+        crate::utils::tests::test_full_problem(24, run, "10282017", "2065");
+    }
 }
@@ -1,6 +1,7 @@
 #[macro_use]
 mod macros;
 
+mod answers;
 mod computer;
 pub mod day01;
 pub mod day02;
@@ -15,25 +16,48 @@ pub mod day10;
 pub mod day11;
 pub mod day12;
 pub mod day13;
+pub mod day14;
 pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+mod maze;
+mod patch;
+mod rom;
+mod solution;
+pub mod symbolic;
 mod utils;
 
-pub use self::computer::Rom;
+pub use self::answers::{Answers, Check};
+pub use self::computer::{ComputerST, Queue, State};
 pub use self::error::Error;
+pub use self::maze::{Maze, Point};
+pub use self::patch::{Patch, PatchSet};
 pub use self::reader::Reader;
+pub use self::rom::{ParseRomError, ParseRomErrorKind, Rom};
+pub use self::solution::{solution, solutions, Solution};
 pub use day13::Game;
+pub use day14::Reactions;
+pub use day16::Signal;
 
 mod error {
     #[derive(Debug)]
     pub enum Error {
         Custom(String),
-        Io(std::io::Error),
+        Io(std::io::Error, Option<std::path::PathBuf>),
         ParseInt(std::num::ParseIntError),
+        ParseRom(crate::rom::ParseRomError),
     }
 
     impl From<std::io::Error> for Error {
         fn from(e: std::io::Error) -> Self {
-            Self::Io(e)
+            Self::Io(e, None)
         }
     }
 
@@ -43,12 +67,20 @@ mod error {
         }
     }
 
+    impl From<crate::rom::ParseRomError> for Error {
+        fn from(e: crate::rom::ParseRomError) -> Self {
+            Self::ParseRom(e)
+        }
+    }
+
     impl std::fmt::Display for Error {
         fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
             match self {
                 Self::Custom(s) => write!(f, "{}", s),
-                Self::Io(e) => write!(f, "{}", e),
+                Self::Io(e, Some(path)) => write!(f, "{}: {}", path.display(), e),
+                Self::Io(e, None) => write!(f, "{}", e),
                 Self::ParseInt(e) => write!(f, "{}", e),
+                Self::ParseRom(e) => write!(f, "{}", e),
             }
         }
     }
@@ -57,26 +89,119 @@ mod error {
 }
 
 mod reader {
+    use std::borrow::Cow;
+    use std::io::{BufRead, BufReader, Cursor, Read};
+    use std::path::Path;
+
+    use crate::error::Error;
 
     pub enum Reader<'a> {
-        File(std::io::BufReader<std::fs::File>),
+        File(BufReader<std::fs::File>),
         Stdin(std::io::StdinLock<'a>),
+        /// Input already in memory, such as a `String` or `include_bytes!`.
+        Memory(Cursor<Cow<'a, [u8]>>),
+        #[cfg(feature = "gzip")]
+        Gzip(Box<BufReader<flate2::read::MultiGzDecoder<std::fs::File>>>),
+        #[cfg(feature = "zstd")]
+        Zstd(Box<BufReader<ZstdDecoder>>),
+    }
+
+    #[cfg(feature = "zstd")]
+    type ZstdDecoder =
+        ruzstd::decoding::StreamingDecoder<std::fs::File, ruzstd::decoding::FrameDecoder>;
+
+    impl Reader<'static> {
+        /// Opens the file at `path`, reporting failures as `Error::Io` along
+        /// with the path. Files ending in `.gz` or `.zst` are decompressed as
+        /// they are read.
+        pub fn open<P>(path: P) -> Result<Self, Error>
+        where
+            P: AsRef<Path>,
+        {
+            let path = path.as_ref();
+            let with_path = |e| Error::Io(e, Some(path.to_path_buf()));
+
+            let file = std::fs::File::open(path).map_err(with_path)?;
+            if file.metadata().map_err(with_path)?.is_dir() {
+                let e = std::io::Error::other("Is a directory");
+                return Err(with_path(e));
+            }
+
+            match path.extension().and_then(|ext| ext.to_str()) {
+                #[cfg(feature = "gzip")]
+                Some("gz") => {
+                    let decoder = flate2::read::MultiGzDecoder::new(file);
+                    Ok(Self::Gzip(Box::new(BufReader::new(decoder))))
+                }
+                #[cfg(feature = "zstd")]
+                Some("zst") => {
+                    let decoder = ruzstd::decoding::StreamingDecoder::new(file)
+                        .map_err(|e| with_path(std::io::Error::other(e)))?;
+                    Ok(Self::Zstd(Box::new(BufReader::new(decoder))))
+                }
+                #[cfg(not(feature = "gzip"))]
+                Some("gz") => bail!(
+                    "{}: Reading .gz inputs requires the `gzip` feature.",
+                    path.display()
+                ),
+                #[cfg(not(feature = "zstd"))]
+                Some("zst") => bail!(
+                    "{}: Reading .zst inputs requires the `zstd` feature.",
+                    path.display()
+                ),
+                _ => Ok(Self::File(BufReader::new(file))),
+            }
+        }
+    }
+
+    impl<'a> From<&'a [u8]> for Reader<'a> {
+        fn from(bytes: &'a [u8]) -> Self {
+            Self::Memory(Cursor::new(Cow::Borrowed(bytes)))
+        }
+    }
+
+    impl<'a> From<&'a str> for Reader<'a> {
+        fn from(s: &'a str) -> Self {
+            Self::from(s.as_bytes())
+        }
+    }
+
+    impl<'a> From<Vec<u8>> for Reader<'a> {
+        fn from(bytes: Vec<u8>) -> Self {
+            Self::Memory(Cursor::new(Cow::Owned(bytes)))
+        }
     }
 
-    impl<'a> std::io::Read for Reader<'a> {
+    impl<'a> From<String> for Reader<'a> {
+        fn from(s: String) -> Self {
+            Self::from(s.into_bytes())
+        }
+    }
+
+    impl<'a> Read for Reader<'a> {
         fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
             match self {
                 Self::File(file) => file.read(buf),
                 Self::Stdin(guard) => guard.read(buf),
+                Self::Memory(cursor) => cursor.read(buf),
+                #[cfg(feature = "gzip")]
+                Self::Gzip(reader) => reader.read(buf),
+                #[cfg(feature = "zstd")]
+                Self::Zstd(reader) => reader.read(buf),
             }
         }
     }
 
-    impl<'a> std::io::BufRead for Reader<'a> {
+    impl<'a> BufRead for Reader<'a> {
         fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
             match self {
                 Self::File(reader) => reader.fill_buf(),
                 Self::Stdin(guard) => guard.fill_buf(),
+                Self::Memory(cursor) => cursor.fill_buf(),
+                #[cfg(feature = "gzip")]
+                Self::Gzip(reader) => reader.fill_buf(),
+                #[cfg(feature = "zstd")]
+                Self::Zstd(reader) => reader.fill_buf(),
             }
         }
 
@@ -84,7 +209,70 @@ mod reader {
             match self {
                 Self::File(reader) => reader.consume(amt),
                 Self::Stdin(guard) => guard.consume(amt),
+                Self::Memory(cursor) => cursor.consume(amt),
+                #[cfg(feature = "gzip")]
+                Self::Gzip(reader) => reader.consume(amt),
+                #[cfg(feature = "zstd")]
+                Self::Zstd(reader) => reader.consume(amt),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn read(mut reader: Reader) -> String {
+            let mut s = String::new();
+            reader.read_to_string(&mut s).unwrap();
+            s
+        }
+
+        #[test]
+        fn test_reader() {
+            let input = "1,0,0,3,99\n";
+            assert_eq!(read(Reader::from(input)), input);
+            assert_eq!(read(Reader::from(input.to_string())), input);
+
+            let dir = std::env::temp_dir().join(format!("aoc2019-reader-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+
+            let path = dir.join("plain.txt");
+            std::fs::write(&path, input).unwrap();
+            assert_eq!(read(Reader::open(&path).unwrap()), input);
+
+            #[cfg(feature = "gzip")]
+            {
+                use std::io::Write;
+
+                let path = dir.join("input.txt.gz");
+                let file = std::fs::File::create(&path).unwrap();
+                let mut encoder =
+                    flate2::write::GzEncoder::new(file, flate2::Compression::default());
+                encoder.write_all(input.as_bytes()).unwrap();
+                encoder.finish().unwrap();
+                assert_eq!(read(Reader::open(&path).unwrap()), input);
             }
+
+            #[cfg(feature = "zstd")]
+            {
+                let path = dir.join("input.txt.zst");
+                let compressed = ruzstd::encoding::compress_to_vec(
+                    input.as_bytes(),
+                    ruzstd::encoding::CompressionLevel::Fastest,
+                );
+                std::fs::write(&path, compressed).unwrap();
+                assert_eq!(read(Reader::open(&path).unwrap()), input);
+            }
+
+            let missing = dir.join("missing.txt");
+            match Reader::open(&missing) {
+                Err(Error::Io(_, Some(path))) => assert_eq!(path, missing),
+                _ => panic!("Expected an Io error for {:?}", missing),
+            }
+            assert!(Reader::open(&dir).is_err());
+
+            std::fs::remove_dir_all(&dir).unwrap();
         }
     }
 }
@@ -1,18 +1,138 @@
 use std::fs;
-use std::io;
-use std::path::PathBuf;
+use std::io::{self, BufRead, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
+use crossbeam::channel;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
 use structopt::StructOpt;
 
-use aoc2019::{self, bail, Error, Reader};
+use aoc2019::{self, bail, error, Answers, Error, Patch, PatchSet, Reader, Solution};
 
 #[derive(Debug, StructOpt)]
 struct Opt {
     /// Day
-    day: usize,
+    day: Option<usize>,
+
+    /// Input file paths, or `-` for stdin; defaults to every input for the
+    /// day in the input directory
+    inputs: Vec<PathBuf>,
+
+    /// Directory searched for dayNN.txt and other dayNN* inputs
+    #[structopt(long, default_value = "input")]
+    input_dir: PathBuf,
+
+    /// Only solve the given part (1 or 2)
+    #[structopt(long)]
+    part: Option<usize>,
+
+    /// Output format: text, json or tsv
+    #[structopt(long, default_value = "text")]
+    format: Format,
+
+    /// Patch file whose edits are applied to the day's Intcode ROM before
+    /// solving
+    #[structopt(long)]
+    patch: Option<PathBuf>,
 
-    /// Input file path, if not supplied will read from stdin
-    input: Option<PathBuf>,
+    /// Only apply the patch with this name from the patch file
+    #[structopt(long, requires = "patch")]
+    patch_name: Option<String>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Run every implemented day against its inputs and time each part
+    All {
+        /// Directory searched for dayNN.txt and other dayNN* inputs
+        #[structopt(long, default_value = "input")]
+        input_dir: PathBuf,
+
+        /// Output format: text, json or tsv
+        #[structopt(long, default_value = "text")]
+        format: Format,
+    },
+
+    /// Check solutions against a file of known answers
+    Verify {
+        /// Only check inputs for this day
+        day: Option<usize>,
+
+        /// Answers file; the inputs it names are read from the same directory
+        #[structopt(long, default_value = "input/answers.txt")]
+        answers: PathBuf,
+    },
+
+    /// Solve one day for every file in a directory of inputs, in parallel
+    Batch {
+        /// Day
+        day: usize,
+
+        /// Directory of inputs for the day
+        dir: PathBuf,
+
+        /// Number of worker threads, defaulting to the number of CPUs
+        #[structopt(long)]
+        jobs: Option<usize>,
+
+        /// Output format: text, json or tsv
+        #[structopt(long, default_value = "text")]
+        format: Format,
+    },
+
+    /// Time one day over repeated runs and report min, median and p95
+    Bench {
+        /// Day
+        day: usize,
+
+        /// Input file path, defaulting to dayNN.txt in the input directory
+        input: Option<PathBuf>,
+
+        /// Directory searched for dayNN.txt
+        #[structopt(long, default_value = "input")]
+        input_dir: PathBuf,
+
+        /// Number of timed runs
+        #[structopt(long, default_value = "20")]
+        runs: usize,
+
+        /// Number of untimed runs before the timed ones
+        #[structopt(long, default_value = "3")]
+        warmup: usize,
+
+        /// Compare against a baseline previously written with --save
+        #[structopt(long)]
+        baseline: Option<PathBuf>,
+
+        /// Write the results as a JSON baseline
+        #[structopt(long)]
+        save: Option<PathBuf>,
+    },
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Tsv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "tsv" => Ok(Format::Tsv),
+            _ => bail!("Unknown format {:?}; expected text, json or tsv.", s),
+        }
+    }
 }
 
 fn main() {
@@ -30,40 +150,704 @@ fn main() {
 fn run() -> Result<(), Error> {
     let opt = Opt::from_args();
 
-    let stdin = io::stdin();
+    match (opt.command, opt.day) {
+        (Some(Command::All { input_dir, format }), _) => all(&input_dir, format),
+        (Some(Command::Verify { day, answers }), _) => verify(day, &answers),
+        (
+            Some(Command::Batch {
+                day,
+                dir,
+                jobs,
+                format,
+            }),
+            _,
+        ) => batch(day, &dir, jobs, format),
+        (
+            Some(Command::Bench {
+                day,
+                input,
+                input_dir,
+                runs,
+                warmup,
+                baseline,
+                save,
+            }),
+            _,
+        ) => {
+            let input = match input {
+                Some(input) => input,
+                None => input_dir.join(format!("day{:02}.txt", day)),
+            };
+            bench(
+                day,
+                &input,
+                runs,
+                warmup,
+                baseline.as_deref(),
+                save.as_deref(),
+            )
+        }
+        (None, Some(day)) => {
+            let patch = match &opt.patch {
+                Some(path) => {
+                    let set = PatchSet::from_reader(Reader::open(path)?)?;
+                    Some(set.select(opt.patch_name.as_deref())?)
+                }
+                None => None,
+            };
+            one(
+                day,
+                opt.inputs,
+                &opt.input_dir,
+                opt.part,
+                opt.format,
+                patch.as_ref(),
+            )
+        }
+        (None, None) => bail!("Must supply either a day or a subcommand; see --help."),
+    }
+}
 
-    let input = match opt.input {
-        Some(path) => {
-            let file = fs::File::open(path).unwrap();
-            let reader = io::BufReader::new(file);
-            Reader::File(reader)
+fn one(
+    day: usize,
+    inputs: Vec<PathBuf>,
+    input_dir: &Path,
+    part: Option<usize>,
+    format: Format,
+    patch: Option<&Patch>,
+) -> Result<(), Error> {
+    let solution = aoc2019::solution(day)?;
+
+    let inputs = if inputs.is_empty() {
+        discover(input_dir, day)?
+    } else {
+        inputs
+    };
+    if inputs.is_empty() {
+        bail!("No input for day {} in {}.", day, input_dir.display());
+    }
+
+    let parts = match part {
+        Some(part) => vec![part],
+        None => vec![1, 2],
+    };
+    let records = inputs
+        .iter()
+        .flat_map(|path| solve_path(solution, path, &parts, patch))
+        .collect::<Vec<_>>();
+
+    match format {
+        Format::Text => {
+            for (i, record) in records.into_iter().enumerate() {
+                if inputs.len() > 1 && i % parts.len() == 0 {
+                    if i > 0 {
+                        println!();
+                    }
+                    println!("==> {} <==", record.input);
+                }
+                // Rendered answers such as day 8's already end in a newline.
+                println!("{}", record.answer?.trim_end_matches('\n'));
+            }
+        }
+        Format::Json | Format::Tsv => {
+            print_records(&records, format)?;
+            check_records(&records)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The outcome of solving one part of one input.
+#[derive(Serialize)]
+struct Record {
+    day: usize,
+    input: String,
+    part: usize,
+    /// Written as an `answer` and an `error`, one of which is null.
+    #[serde(flatten, serialize_with = "serialize_answer")]
+    answer: Result<String, Error>,
+    /// Time spent parsing the input, which is shared by both parts.
+    #[serde(rename = "parse_ns", serialize_with = "serialize_nanos")]
+    parse: Duration,
+    #[serde(rename = "time_ns", serialize_with = "serialize_nanos")]
+    time: Duration,
+}
+
+fn serialize_answer<S>(answer: &Result<String, Error>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(2))?;
+    match answer {
+        Ok(answer) => {
+            map.serialize_entry("answer", answer)?;
+            map.serialize_entry("error", &None::<String>)?;
+        }
+        Err(e) => {
+            map.serialize_entry("answer", &None::<String>)?;
+            map.serialize_entry("error", &e.to_string())?;
+        }
+    }
+    map.end()
+}
+
+fn serialize_nanos<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u128(duration.as_nanos())
+}
+
+fn timed<T, F>(elapsed: &mut Duration, f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    let start = Instant::now();
+    let res = f();
+    *elapsed = start.elapsed();
+    res
+}
+
+/// Returns every input for `day` in `dir`: `dayNN.txt` and any other file
+/// whose name starts with `dayNN` not followed by another digit, such as
+/// `dayNN-alice.txt`.
+fn discover(dir: &Path, day: usize) -> Result<Vec<PathBuf>, Error> {
+    let prefix = format!("day{:02}", day);
+    let with_path = |e| Error::Io(e, Some(dir.to_path_buf()));
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).map_err(with_path)? {
+        let entry = entry.map_err(with_path)?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let matches = name.starts_with(&prefix)
+            && !name[prefix.len()..].starts_with(|c: char| c.is_ascii_digit());
+        if matches && entry.file_type().map_err(with_path)?.is_file() {
+            paths.push(entry.path());
+        }
+    }
+
+    // Put the canonical `dayNN.txt` first, then the rest by name.
+    let canonical = dir.join(format!("{}.txt", prefix));
+    paths.sort_by_key(|path| (*path != canonical, path.clone()));
+
+    Ok(paths)
+}
+
+/// Solves `parts` of the input at `path`, where `-` means stdin, after
+/// applying `patch` to it if there is one.
+fn solve_path(
+    solution: &dyn Solution,
+    path: &Path,
+    parts: &[usize],
+    patch: Option<&Patch>,
+) -> Vec<Record> {
+    let name = path.display().to_string();
+
+    if path == Path::new("-") {
+        let stdin = io::stdin();
+        let mut input = Reader::Stdin(stdin.lock());
+        return solve(solution, &name, &mut input, parts, patch);
+    }
+
+    match Reader::open(path) {
+        Ok(mut input) => solve(solution, &name, &mut input, parts, patch),
+        Err(e) => unsolved(solution, &name, parts, e),
+    }
+}
+
+/// Parses `input` once and solves each of `parts` from it. If there is a
+/// `patch`, the input is read as a ROM and patched first.
+fn solve(
+    solution: &dyn Solution,
+    name: &str,
+    input: &mut dyn BufRead,
+    parts: &[usize],
+    patch: Option<&Patch>,
+) -> Vec<Record> {
+    let mut parse = Duration::default();
+    let parsed = match patch {
+        Some(patch) => patch.apply_to_text(input).and_then(|patched| {
+            let mut input = Reader::from(patched);
+            timed(&mut parse, || solution.parse(&mut input))
+        }),
+        None => timed(&mut parse, || solution.parse(input)),
+    };
+    let parsed = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let mut records = unsolved(solution, name, parts, e);
+            for record in &mut records {
+                record.parse = parse;
+            }
+            return records;
+        }
+    };
+
+    parts
+        .iter()
+        .map(|&part| {
+            let mut time = Duration::default();
+            let answer = timed(&mut time, || solution.part(part, &parsed));
+            Record {
+                day: solution.day(),
+                input: name.to_string(),
+                part,
+                answer,
+                parse,
+                time,
+            }
+        })
+        .collect()
+}
+
+/// Records for `parts` of an input that could not be read or parsed. The
+/// first part keeps `e` itself, so its variant survives, and the rest get a
+/// copy of its message.
+fn unsolved(solution: &dyn Solution, name: &str, parts: &[usize], e: Error) -> Vec<Record> {
+    let message = e.to_string();
+    let mut e = Some(e);
+
+    parts
+        .iter()
+        .map(|&part| Record {
+            day: solution.day(),
+            input: name.to_string(),
+            part,
+            answer: Err(e.take().unwrap_or_else(|| error!("{}", message))),
+            parse: Duration::default(),
+            time: Duration::default(),
+        })
+        .collect()
+}
+
+fn check_records(records: &[Record]) -> Result<(), Error> {
+    let failures = records.iter().filter(|r| r.answer.is_err()).count();
+    if failures > 0 {
+        bail!("{} of {} part(s) failed.", failures, records.len());
+    }
+    Ok(())
+}
+
+fn print_records(records: &[Record], format: Format) -> Result<(), Error> {
+    match format {
+        Format::Text => print_table(records),
+        Format::Json => {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            serde_json::to_writer(&mut stdout, records).map_err(|e| error!("{}", e))?;
+            println!();
+        }
+        Format::Tsv => {
+            println!("day\tinput\tpart\tanswer\tparse_ns\ttime_ns\terror");
+            for record in records {
+                let (answer, error) = match &record.answer {
+                    Ok(answer) => (tsv_field(answer), String::new()),
+                    Err(e) => (String::new(), tsv_field(&e.to_string())),
+                };
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    record.day,
+                    tsv_field(&record.input),
+                    record.part,
+                    answer,
+                    record.parse.as_nanos(),
+                    record.time.as_nanos(),
+                    error
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Escapes the characters that would break a TSV row.
+fn tsv_field(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Column widths of the `all` table, which are separated by `GAP` spaces.
+const DAY_WIDTH: usize = 3;
+const INPUT_WIDTH: usize = 16;
+const TIME_WIDTH: usize = 10;
+const ANSWER_WIDTH: usize = 16;
+const GAP: usize = 2;
+
+/// Prints the records of `all` as a table with one row per input.
+fn print_table(records: &[Record]) {
+    println!(
+        "{:>dw$}  {:<iw$}  {:>tw$}  {:>tw$}  {:>tw$}  {:>tw$}  {:<aw$}  Answer 2",
+        "Day",
+        "Input",
+        "Parse",
+        "Part 1",
+        "Part 2",
+        "Total",
+        "Answer 1",
+        dw = DAY_WIDTH,
+        iw = INPUT_WIDTH,
+        tw = TIME_WIDTH,
+        aw = ANSWER_WIDTH,
+    );
+
+    // Where the answer columns start, for answers that take several lines.
+    let indent = DAY_WIDTH + INPUT_WIDTH + 4 * TIME_WIDTH + 6 * GAP;
+    let mut total = Duration::default();
+
+    for input in records.chunks(2) {
+        let (one, two) = (&input[0], &input[1]);
+        let input_total = one.parse + one.time + two.time;
+        total += input_total;
+
+        let name = Path::new(&one.input).file_name().map_or_else(
+            || one.input.clone(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+
+        print!(
+            "{:>dw$}  {:<iw$}  {:>tw$.1?}  {:>tw$.1?}  {:>tw$.1?}  {:>tw$.1?}  ",
+            one.day,
+            name,
+            one.parse,
+            one.time,
+            two.time,
+            input_total,
+            dw = DAY_WIDTH,
+            iw = INPUT_WIDTH,
+            tw = TIME_WIDTH,
+        );
+
+        // Rendered answers such as day 8's continue below their column.
+        let (lines1, lines2) = (cell_lines(&one.answer), cell_lines(&two.answer));
+        for i in 0..lines1.len().max(lines2.len()) {
+            if i > 0 {
+                print!("{:indent$}", "", indent = indent);
+            }
+            let line1 = lines1.get(i).map_or("", String::as_str);
+            let line2 = lines2.get(i).map_or("", String::as_str);
+            println!("{:<aw$}  {}", line1, line2, aw = ANSWER_WIDTH);
+        }
+    }
+
+    println!(
+        "{:>dw$}  {:<iw$}  {:>tw$}  {:>tw$}  {:>tw$}  {:>tw$.1?}",
+        "",
+        "",
+        "",
+        "",
+        "",
+        total,
+        dw = DAY_WIDTH,
+        iw = INPUT_WIDTH,
+        tw = TIME_WIDTH,
+    );
+}
+
+/// Returns the lines to show in a part's answer column: the answer, or the
+/// error that kept it from being solved.
+fn cell_lines(answer: &Result<String, Error>) -> Vec<String> {
+    match answer {
+        Ok(answer) => answer.lines().map(String::from).collect(),
+        Err(e) => vec![format!("error: {}", e)],
+    }
+}
+
+fn all(input_dir: &Path, format: Format) -> Result<(), Error> {
+    let mut records = Vec::new();
+    let dir = input_dir.display().to_string();
+
+    for solution in aoc2019::solutions() {
+        let inputs = match discover(input_dir, solution.day()) {
+            Ok(inputs) => inputs,
+            Err(e) => {
+                records.extend(unsolved(solution, &dir, &[1, 2], e));
+                continue;
+            }
+        };
+        if inputs.is_empty() {
+            let e = error!(
+                "No input for day {} in {}.",
+                solution.day(),
+                input_dir.display()
+            );
+            records.extend(unsolved(solution, &dir, &[1, 2], e));
+        }
+        for path in inputs {
+            records.extend(solve_path(solution, &path, &[1, 2], None));
+        }
+    }
+
+    print_records(&records, format)?;
+
+    check_records(&records)
+}
+
+fn verify(day: Option<usize>, path: &Path) -> Result<(), Error> {
+    let answers = Answers::from_reader(Reader::open(path)?)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let checks = answers.check(dir, day);
+    if checks.is_empty() {
+        bail!("No answers to check in {}.", path.display());
+    }
+
+    println!("{:>3}  {:<20}  {:>4}  Result", "Day", "Input", "Part");
+
+    let mut failures = 0;
+    for check in &checks {
+        let result = match &check.actual {
+            Ok(_) if check.passed() => "ok".to_string(),
+            Ok(actual) => format!("FAILED: expected {:?}, got {:?}", check.expected, actual),
+            Err(e) => format!("FAILED: {}", e),
+        };
+        if !check.passed() {
+            failures += 1;
+        }
+        println!(
+            "{:>3}  {:<20}  {:>4}  {}",
+            check.day, check.name, check.part, result
+        );
+    }
+
+    if failures > 0 {
+        bail!("{} of {} answer(s) did not match.", failures, checks.len());
+    }
+
+    Ok(())
+}
+
+fn batch(day: usize, dir: &Path, jobs: Option<usize>, format: Format) -> Result<(), Error> {
+    let solution = aoc2019::solution(day)?;
+    let with_path = |e| Error::Io(e, Some(dir.to_path_buf()));
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).map_err(with_path)? {
+        let entry = entry.map_err(with_path)?;
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !hidden && entry.file_type().map_err(with_path)?.is_file() {
+            paths.push(entry.path());
         }
-        None => {
-            let guard = stdin.lock();
-            Reader::Stdin(guard)
+    }
+    paths.sort();
+    if paths.is_empty() {
+        bail!("No inputs in {}.", dir.display());
+    }
+
+    let jobs = jobs
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .clamp(1, paths.len());
+
+    let (tx_path, rx_path) = channel::unbounded();
+    for (i, path) in paths.iter().enumerate() {
+        tx_path.send((i, path)).unwrap();
+    }
+    drop(tx_path);
+
+    let start = Instant::now();
+    let (tx_records, rx_records) = channel::unbounded();
+    crossbeam::thread::scope(|s| {
+        for _ in 0..jobs {
+            let rx_path = rx_path.clone();
+            let tx_records = tx_records.clone();
+            s.spawn(move |_| {
+                for (i, path) in rx_path.iter() {
+                    tx_records
+                        .send((i, solve_path(solution, path, &[1, 2], None)))
+                        .unwrap();
+                }
+            });
+        }
+    })
+    .map_err(|_| error!("A worker thread panicked."))?;
+    drop(tx_records);
+    let elapsed = start.elapsed();
+
+    let mut results = rx_records.iter().collect::<Vec<_>>();
+    results.sort_by_key(|(i, _)| *i);
+    let records = results
+        .into_iter()
+        .flat_map(|(_, records)| records)
+        .collect::<Vec<_>>();
+
+    print_records(&records, format)?;
+
+    if format == Format::Text {
+        println!(
+            "Solved {} input(s) on {} thread(s) in {:.1?}.",
+            paths.len(),
+            jobs,
+            elapsed
+        );
+
+        let failures = records
+            .chunks(2)
+            .filter_map(|input| match (&input[0].answer, &input[1].answer) {
+                (Err(e), _) | (_, Err(e)) => Some((&input[0].input, e)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if !failures.is_empty() {
+            println!();
+            println!("FAILED ({} of {} inputs):", failures.len(), paths.len());
+            for (input, e) in failures {
+                println!("  {}: {}", input, e);
+            }
         }
+    }
+
+    check_records(&records)
+}
+
+/// Summary statistics of one stage of a benchmark, in nanoseconds.
+#[derive(Debug, Deserialize, Serialize)]
+struct Stats {
+    stage: String,
+    min_ns: u64,
+    median_ns: u64,
+    p95_ns: u64,
+}
+
+impl Stats {
+    fn new(stage: &str, mut samples: Vec<Duration>) -> Self {
+        samples.sort();
+        let ns = |d: Duration| d.as_nanos() as u64;
+
+        let n = samples.len();
+        let median = if n % 2 == 0 {
+            (samples[n / 2 - 1] + samples[n / 2]) / 2
+        } else {
+            samples[n / 2]
+        };
+        // Nearest-rank percentile.
+        let p95 = samples[(n * 95).div_ceil(100) - 1];
+
+        Self {
+            stage: stage.to_string(),
+            min_ns: ns(samples[0]),
+            median_ns: ns(median),
+            p95_ns: ns(p95),
+        }
+    }
+}
+
+/// Benchmark results, as saved with `bench --save`.
+#[derive(Debug, Deserialize, Serialize)]
+struct Baseline {
+    day: usize,
+    runs: usize,
+    stages: Vec<Stats>,
+}
+
+fn bench(
+    day: usize,
+    path: &Path,
+    runs: usize,
+    warmup: usize,
+    baseline: Option<&Path>,
+    save: Option<&Path>,
+) -> Result<(), Error> {
+    if runs == 0 {
+        bail!("Must time at least one run.");
+    }
+
+    let solution = aoc2019::solution(day)?;
+
+    let baseline = match baseline {
+        Some(path) => {
+            let baseline: Baseline = serde_json::from_reader(Reader::open(path)?)
+                .map_err(|e| error!("{}: {}", path.display(), e))?;
+            if baseline.day != day {
+                bail!(
+                    "{} is a baseline for day {}, not day {}.",
+                    path.display(),
+                    baseline.day,
+                    day
+                );
+            }
+            Some(baseline)
+        }
+        None => None,
     };
 
-    let (answer1, answer2) = match opt.day {
-        1 => aoc2019::day01::run(input)?,
-        2 => aoc2019::day02::run(input)?,
-        3 => aoc2019::day03::run(input)?,
-        4 => aoc2019::day04::run(input)?,
-        5 => aoc2019::day05::run(input)?,
-        6 => aoc2019::day06::run(input)?,
-        7 => aoc2019::day07::run(input)?,
-        8 => aoc2019::day08::run(input)?,
-        9 => aoc2019::day09::run(input)?,
-        10 => aoc2019::day10::run(input)?,
-        11 => aoc2019::day11::run(input)?,
-        12 => aoc2019::day12::run(input)?,
-        13 => aoc2019::day13::run(input)?,
-        15 => aoc2019::day15::run(input)?,
-        n if n > 0 && n < 26 => bail!("Day {} is not yet implemented", n),
-        _ => bail!("Day must be between 1 and 25, inclusive."),
+    // Read the input up front so that only parsing and solving are timed.
+    let mut bytes = Vec::new();
+    Reader::open(path)?.read_to_end(&mut bytes)?;
+
+    let mut samples = [Vec::new(), Vec::new(), Vec::new()];
+    for run in 0..warmup + runs {
+        let mut times = [Duration::default(); 3];
+        let parsed = timed(&mut times[0], || {
+            solution.parse(&mut Reader::from(&bytes[..]))
+        })?;
+        timed(&mut times[1], || solution.part_one(&parsed))?;
+        timed(&mut times[2], || solution.part_two(&parsed))?;
+
+        if run >= warmup {
+            for (samples, time) in samples.iter_mut().zip(times.iter()) {
+                samples.push(*time);
+            }
+        }
+    }
+
+    let [parse, part_one, part_two] = samples;
+    let results = Baseline {
+        day,
+        runs,
+        stages: vec![
+            Stats::new("parse", parse),
+            Stats::new("part 1", part_one),
+            Stats::new("part 2", part_two),
+        ],
     };
 
-    println!("{}\n{}", answer1, answer2);
+    println!(
+        "Day {} ({}), {} run(s) after {} warmup run(s)",
+        day,
+        path.display(),
+        runs,
+        warmup
+    );
+    println!(
+        "{:<6}  {:>10}  {:>10}  {:>10}  {:>10}  {:>7}",
+        "Stage", "Min", "Median", "p95", "Baseline", "Change"
+    );
+    for stats in &results.stages {
+        let old = baseline
+            .as_ref()
+            .and_then(|b| b.stages.iter().find(|old| old.stage == stats.stage));
+        let (old_median, change) = match old {
+            Some(old) if old.median_ns > 0 => {
+                let change = (stats.median_ns as f64 / old.median_ns as f64 - 1.0) * 100.0;
+                (
+                    format!("{:.1?}", Duration::from_nanos(old.median_ns)),
+                    format!("{:+.1}%", change),
+                )
+            }
+            _ => (String::new(), String::new()),
+        };
+        let row = format!(
+            "{:<6}  {:>10.1?}  {:>10.1?}  {:>10.1?}  {:>10}  {:>7}",
+            stats.stage,
+            Duration::from_nanos(stats.min_ns),
+            Duration::from_nanos(stats.median_ns),
+            Duration::from_nanos(stats.p95_ns),
+            old_median,
+            change
+        );
+        println!("{}", row.trim_end());
+    }
+
+    if let Some(path) = save {
+        let file = fs::File::create(path).map_err(|e| Error::Io(e, Some(path.to_path_buf())))?;
+        serde_json::to_writer_pretty(file, &results)
+            .map_err(|e| error!("{}: {}", path.display(), e))?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+
+/// A cell of a `Maze`, as `(x, y)` with `y` counting down from the top row.
+pub type Point = (usize, usize);
+
+/// A rectangular grid of ASCII cells, such as the vault on day 18 or the
+/// donut maze on day 20.
+///
+/// Rows shorter than the longest are padded with spaces.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Maze {
+    cells: Vec<u8>,
+    rows: usize,
+    cols: usize,
+}
+
+impl Maze {
+    pub fn from_reader<R>(mut reader: R) -> Result<Self, Error>
+    where
+        R: std::io::BufRead,
+    {
+        let mut buf = String::new();
+        reader.read_to_string(&mut buf)?;
+        buf.parse()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the cell at `(x, y)`, or `None` if it is outside the maze.
+    pub fn get(&self, (x, y): Point) -> Option<u8> {
+        if x < self.cols && y < self.rows {
+            Some(self.cells[y * self.cols + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, (x, y): Point, cell: u8) -> Result<(), Error> {
+        if x >= self.cols || y >= self.rows {
+            bail!(
+                "({}, {}) is outside the {}x{} maze.",
+                x,
+                y,
+                self.cols,
+                self.rows
+            );
+        }
+        self.cells[y * self.cols + x] = cell;
+        Ok(())
+    }
+
+    /// Iterates over every cell along with its position, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = (Point, u8)> + '_ {
+        let cols = self.cols;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, &cell)| ((i % cols, i / cols), cell))
+    }
+
+    /// Returns the positions of every cell equal to `cell`.
+    pub fn find(&self, cell: u8) -> Vec<Point> {
+        self.cells()
+            .filter(|&(_, other)| other == cell)
+            .map(|(point, _)| point)
+            .collect()
+    }
+
+    /// Returns the positions above, right of, below and left of `point` that
+    /// lie inside the maze.
+    pub fn neighbors(&self, (x, y): Point) -> impl Iterator<Item = Point> {
+        let (rows, cols) = (self.rows, self.cols);
+        let candidates = [
+            y.checked_sub(1).map(|y| (x, y)),
+            Some((x + 1, y)),
+            Some((x, y + 1)),
+            x.checked_sub(1).map(|x| (x, y)),
+        ];
+        IntoIterator::into_iter(candidates)
+            .flatten()
+            .filter(move |&(x, y)| x < cols && y < rows)
+    }
+
+    /// Breadth first search from `start`, returning every reachable cell with
+    /// its distance, in order of distance.
+    ///
+    /// Each path carries a value, starting as `init`. Moving onto a cell calls
+    /// `step` with the new position, its contents and the value so far, which
+    /// returns the value to carry on or `None` if the cell can't be entered.
+    /// Only the first path to reach a cell is followed.
+    pub fn bfs<T, F>(&self, start: Point, init: T, mut step: F) -> Vec<(Point, usize, T)>
+    where
+        T: Clone,
+        F: FnMut(Point, u8, &T) -> Option<T>,
+    {
+        let mut visited = vec![false; self.cells.len()];
+        let mut queue = VecDeque::new();
+        let mut reached = Vec::new();
+
+        visited[start.1 * self.cols + start.0] = true;
+        queue.push_back((start, 0, init));
+
+        while let Some((point, dist, value)) = queue.pop_front() {
+            for next in self.neighbors(point) {
+                let i = next.1 * self.cols + next.0;
+                if visited[i] {
+                    continue;
+                }
+                visited[i] = true;
+
+                if let Some(value) = step(next, self.cells[i], &value) {
+                    queue.push_back((next, dist + 1, value));
+                }
+            }
+            reached.push((point, dist, value));
+        }
+
+        reached
+    }
+}
+
+impl FromStr for Maze {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .collect::<Vec<_>>();
+        let rows = match lines.iter().rposition(|line| !line.trim().is_empty()) {
+            Some(n) => n + 1,
+            None => bail!("Maze is empty."),
+        };
+        let cols = lines[..rows].iter().map(|line| line.len()).max().unwrap();
+
+        let mut cells = vec![b' '; rows * cols];
+        for (y, line) in lines[..rows].iter().enumerate() {
+            if !line.is_ascii() {
+                bail!("Maze contains non-ASCII characters on line {}.", y + 1);
+            }
+            cells[y * cols..y * cols + line.len()].copy_from_slice(line.as_bytes());
+        }
+
+        Ok(Self { cells, rows, cols })
+    }
+}
+
+impl fmt::Display for Maze {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.cells.chunks(self.cols) {
+            writeln!(f, "{}", String::from_utf8_lossy(row).trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maze() {
+        let maze = "#####\n#a.b#\n#.#\n#####\n\n".parse::<Maze>().unwrap();
+        assert_eq!((maze.cols(), maze.rows()), (5, 4));
+        assert_eq!(maze.get((1, 1)), Some(b'a'));
+        assert_eq!(maze.get((4, 2)), Some(b' '));
+        assert_eq!(maze.get((5, 0)), None);
+        assert_eq!(maze.find(b'b'), vec![(3, 1)]);
+        assert_eq!(
+            maze.neighbors((0, 0)).collect::<Vec<_>>(),
+            vec![(1, 0), (0, 1)]
+        );
+
+        // Count the keys passed on the way to each open cell.
+        let reached = maze.bfs((1, 1), 0, |_, cell, &keys| match cell {
+            b'#' | b' ' => None,
+            b'a'..=b'z' => Some(keys + 1),
+            _ => Some(keys),
+        });
+        assert_eq!(
+            reached,
+            vec![
+                ((1, 1), 0, 0),
+                ((2, 1), 1, 0),
+                ((1, 2), 1, 0),
+                ((3, 1), 2, 1)
+            ]
+        );
+
+        assert_eq!(maze.to_string(), "#####\n#a.b#\n#.#\n#####\n");
+        assert!("\n  \n".parse::<Maze>().is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use std::fmt;
+
+use crate::computer::MAX_RAM;
+use crate::error::Error;
+use crate::rom::Rom;
+
+/// A list of `(address, value)` edits to make to a `Rom` or a running
+/// computer's memory, such as day 13's free play mode.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Patch(Vec<(u64, i64)>);
+
+impl Patch {
+    pub fn new<I>(edits: I) -> Self
+    where
+        I: IntoIterator<Item = (u64, i64)>,
+    {
+        Self(edits.into_iter().collect())
+    }
+
+    /// Returns the edits needed to turn `old` into `new`. Memory past the end
+    /// of a ROM is treated as zero, so trailing zeros don't produce edits.
+    pub fn diff(old: &Rom, new: &Rom) -> Self {
+        let len = std::cmp::max(old.len(), new.len());
+        let edits = (0..len)
+            .filter_map(|i| {
+                let a = old.get(i).copied().unwrap_or(0);
+                let b = new.get(i).copied().unwrap_or(0);
+                if a != b {
+                    Some((i as u64, b))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Self(edits)
+    }
+
+    pub fn edits(&self) -> &[(u64, i64)] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Reads a ROM in text form, applies the patch, and writes it back out as
+    /// text, ready to be handed to a day that parses its own ROM.
+    pub fn apply_to_text<R>(&self, reader: R) -> Result<String, Error>
+    where
+        R: std::io::BufRead,
+    {
+        Ok(Rom::from_reader(reader)?.patched(self)?.to_string())
+    }
+}
+
+impl Rom {
+    /// Applies `patch` in place, growing the ROM if an edit is past its end.
+    pub fn patch(&mut self, patch: &Patch) -> Result<(), Error> {
+        for &(ptr, val) in patch.edits() {
+            if ptr >= MAX_RAM {
+                bail!(
+                    "Cannot patch address {}, which exceeds the maximum of {}.",
+                    ptr,
+                    MAX_RAM - 1
+                );
+            }
+            let ptr = ptr as usize;
+            if ptr >= self.0.len() {
+                self.0.resize(ptr + 1, 0);
+            }
+            self.0[ptr] = val;
+        }
+
+        Ok(())
+    }
+
+    /// Returns a copy of the ROM with `patch` applied.
+    pub fn patched(&self, patch: &Patch) -> Result<Rom, Error> {
+        let mut rom = self.clone();
+        rom.patch(patch)?;
+        Ok(rom)
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (ptr, val) in &self.0 {
+            writeln!(f, "{} = {}", ptr, val)?;
+        }
+        Ok(())
+    }
+}
+
+/// A collection of named patches, as read from a patch file.
+///
+/// Patch files consist of `[name]` headers each followed by
+/// `address = value` lines. Blank lines and anything after a `#` are ignored.
+///
+/// ```text
+/// # Day 13
+/// [free-play]
+/// 0 = 2
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PatchSet(Vec<(String, Patch)>);
+
+impl PatchSet {
+    pub fn from_reader<R>(reader: R) -> Result<Self, Error>
+    where
+        R: std::io::BufRead,
+    {
+        let mut set = PatchSet::default();
+
+        for (i, res) in reader.lines().enumerate() {
+            let line = res?;
+            let line = match line.find('#') {
+                Some(n) => &line[..n],
+                None => &line[..],
+            }
+            .trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let name = line[1..line.len() - 1].trim();
+                if name.is_empty() {
+                    bail!("Empty patch name on line {}.", i + 1);
+                }
+                if set.get(name).is_some() {
+                    bail!("Duplicate patch {:?} on line {}.", name, i + 1);
+                }
+                set.0.push((name.to_string(), Patch::default()));
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let (ptr, val) = match (parts.next(), parts.next()) {
+                (Some(ptr), Some(val)) => (ptr.trim(), val.trim()),
+                _ => bail!("Expected `address = value` on line {}: {:?}", i + 1, line),
+            };
+            let ptr = ptr
+                .parse::<u64>()
+                .map_err(|e| error!("Invalid address {:?} on line {}: {}", ptr, i + 1, e))?;
+            let val = val
+                .parse::<i64>()
+                .map_err(|e| error!("Invalid value {:?} on line {}: {}", val, i + 1, e))?;
+
+            match set.0.last_mut() {
+                Some((_, patch)) => patch.0.push((ptr, val)),
+                None => bail!("Edit on line {} comes before any `[name]` header.", i + 1),
+            }
+        }
+
+        Ok(set)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Patch> {
+        self.0
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, patch)| patch)
+    }
+
+    /// Returns the patch called `name`, or when `name` is `None`, every
+    /// patch in the set combined in order.
+    pub fn select(&self, name: Option<&str>) -> Result<Patch, Error> {
+        match name {
+            Some(name) => self
+                .get(name)
+                .cloned()
+                .ok_or_else(|| error!("No patch named {:?}.", name)),
+            None => Ok(Patch::new(
+                self.iter()
+                    .flat_map(|(_, patch)| patch.edits().iter().copied()),
+            )),
+        }
+    }
+
+    pub fn insert<S>(&mut self, name: S, patch: Patch)
+    where
+        S: Into<String>,
+    {
+        let name = name.into();
+        match self.0.iter_mut().find(|(other, _)| *other == name) {
+            Some((_, old)) => *old = patch,
+            None => self.0.push((name, patch)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Patch)> {
+        self.0.iter().map(|(name, patch)| (name.as_str(), patch))
+    }
+}
+
+impl fmt::Display for PatchSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, patch)) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{}]", name)?;
+            write!(f, "{}", patch)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patch() {
+        let old = "1,0,0,3,99".parse::<Rom>().unwrap();
+        let patch = Patch::new(vec![(1, 12), (2, 2), (6, 7)]);
+        let new = old.patched(&patch).unwrap();
+        assert_eq!(&new[..], &[1, 12, 2, 3, 99, 0, 7]);
+        assert_eq!(Patch::diff(&old, &new), patch);
+        assert!(Patch::diff(&new, &new).is_empty());
+
+        assert!(old.patched(&Patch::new(vec![(MAX_RAM, 1)])).is_err());
+    }
+
+    #[test]
+    fn test_patch_set() {
+        let input = "# Puzzle patches\n[program-alarm]\n1 = 12\n2=2 # verb\n\n[free-play]\n0 = 2\n";
+        let reader = std::io::BufReader::new(input.as_bytes());
+        let set = PatchSet::from_reader(reader).unwrap();
+
+        assert_eq!(
+            set.get("program-alarm"),
+            Some(&Patch::new(vec![(1, 12), (2, 2)]))
+        );
+        assert_eq!(set.get("free-play"), Some(&Patch::new(vec![(0, 2)])));
+        assert_eq!(set.get("missing"), None);
+
+        assert_eq!(
+            set.select(None).unwrap(),
+            Patch::new(vec![(1, 12), (2, 2), (0, 2)])
+        );
+        assert_eq!(
+            set.select(Some("free-play")).unwrap(),
+            Patch::new(vec![(0, 2)])
+        );
+        assert!(set.select(Some("missing")).is_err());
+
+        let patch = set.select(Some("program-alarm")).unwrap();
+        let text = patch.apply_to_text("1,0,0,3,99".as_bytes()).unwrap();
+        assert_eq!(text, "1,12,2,3,99");
+
+        let output = set.to_string();
+        let reader = std::io::BufReader::new(output.as_bytes());
+        assert_eq!(PatchSet::from_reader(reader).unwrap(), set);
+
+        let bad_inputs = &[
+            "0 = 2",
+            "[a]\n0 = x",
+            "[a]\n-1 = 2",
+            "[a]\n0 2",
+            "[]\n0 = 2",
+            "[a]\n[a]",
+        ];
+        for input in bad_inputs {
+            let reader = std::io::BufReader::new(input.as_bytes());
+            assert!(PatchSet::from_reader(reader).is_err(), "{:?}", input);
+        }
+    }
+}
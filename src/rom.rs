@@ -0,0 +1,450 @@
+use std::fmt;
+use std::num::ParseIntError;
+
+use crate::error::Error;
+
+/// An Intcode program, as read from a puzzle input.
+///
+/// The text format is a comma-separated list of integers. Values may be
+/// separated by any amount of whitespace (including newlines), anything from
+/// a `#` to the end of a line is ignored, and a trailing comma is allowed.
+#[derive(Clone, Debug)]
+pub struct Rom(pub(crate) Vec<i64>);
+
+impl Rom {
+    pub fn from_reader<R>(mut reader: R) -> Result<Self, Error>
+    where
+        R: std::io::BufRead,
+    {
+        let mut buf = String::new();
+        reader.read_to_string(&mut buf)?;
+        let rom = buf.parse::<Rom>()?;
+        Ok(rom)
+    }
+}
+
+impl Rom {
+    /// Writes the ROM in the compact binary format.
+    ///
+    /// The format is the magic bytes `ICR`, a version byte, the number of
+    /// words as a LEB128 varint, each word zigzag-encoded as a LEB128 varint,
+    /// and finally a little-endian Adler-32 checksum of everything before it.
+    pub fn write_binary<W>(&self, mut writer: W) -> Result<(), Error>
+    where
+        W: std::io::Write,
+    {
+        let mut buf = Vec::with_capacity(MAGIC.len() + 1 + self.0.len() + 4);
+        buf.extend_from_slice(MAGIC);
+        buf.push(VERSION);
+        write_varint(&mut buf, self.0.len() as u64);
+        for &val in &self.0 {
+            write_varint(&mut buf, zigzag_encode(val));
+        }
+        let checksum = adler32(&buf);
+        buf.extend_from_slice(&checksum.to_le_bytes());
+
+        writer.write_all(&buf)?;
+        Ok(())
+    }
+
+    /// Reads a ROM written by `write_binary`.
+    pub fn read_binary<R>(mut reader: R) -> Result<Self, Error>
+    where
+        R: std::io::Read,
+    {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        if buf.len() < MAGIC.len() + 1 + 4 {
+            bail!("Binary ROM is truncated ({} bytes).", buf.len());
+        }
+        let (body, checksum) = buf.split_at(buf.len() - 4);
+        let expected = u32::from_le_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+        let actual = adler32(body);
+        if actual != expected {
+            bail!(
+                "Binary ROM checksum mismatch: expected {:#010x}, found {:#010x}.",
+                expected,
+                actual
+            );
+        }
+
+        if &body[..MAGIC.len()] != MAGIC {
+            bail!("Binary ROM does not start with {:?}.", MAGIC);
+        }
+        let version = body[MAGIC.len()];
+        if version != VERSION {
+            bail!("Unsupported binary ROM version {}.", version);
+        }
+
+        let mut bytes = body[MAGIC.len() + 1..].iter();
+        let len = read_varint(&mut bytes)?;
+
+        // Each word takes at least one byte, so don't trust a length that
+        // couldn't possibly fit in what's left.
+        if len > bytes.len() as u64 {
+            bail!("Binary ROM claims {} words but is too short.", len);
+        }
+
+        let mut vec = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            vec.push(zigzag_decode(read_varint(&mut bytes)?));
+        }
+
+        if bytes.len() != 0 {
+            bail!("Binary ROM has {} trailing bytes.", bytes.len());
+        }
+
+        Ok(Rom(vec))
+    }
+}
+
+impl From<Vec<i64>> for Rom {
+    fn from(vec: Vec<i64>) -> Self {
+        Rom(vec)
+    }
+}
+
+/// Writes the ROM in the canonical comma-separated format, which can be read
+/// back with `from_reader` or `parse`.
+impl fmt::Display for Rom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, val) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", val)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Rom {
+    type Err = ParseRomError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::default();
+        let mut in_comment = false;
+
+        for (offset, c) in s.char_indices() {
+            if c == '\n' {
+                parser.line += 1;
+                in_comment = false;
+                continue;
+            }
+            if in_comment {
+                continue;
+            }
+
+            match c {
+                '#' => in_comment = true,
+                ',' => parser.finish(s, offset)?,
+                c if c.is_whitespace() => (),
+                c => parser.push(offset, c),
+            }
+        }
+
+        // A trailing comma leaves nothing behind it, which is fine as long as
+        // there was at least one value before it.
+        if parser.token.is_some() || parser.values.is_empty() {
+            parser.finish(s, s.len())?;
+        }
+
+        Ok(Rom(parser.values))
+    }
+}
+
+impl AsRef<[i64]> for Rom {
+    fn as_ref(&self) -> &[i64] {
+        &self.0
+    }
+}
+
+impl std::ops::Deref for Rom {
+    type Target = [i64];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for Rom {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[derive(Debug)]
+struct Parser {
+    values: Vec<i64>,
+    /// Current line, starting at 1
+    line: usize,
+    /// Byte range and starting line of the token currently being read
+    token: Option<(usize, usize, usize)>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            line: 1,
+            token: None,
+        }
+    }
+}
+
+impl Parser {
+    fn push(&mut self, offset: usize, c: char) {
+        let end = offset + c.len_utf8();
+        self.token = match self.token {
+            Some((start, _, line)) => Some((start, end, line)),
+            None => Some((offset, end, self.line)),
+        };
+    }
+
+    fn finish(&mut self, s: &str, offset: usize) -> Result<(), ParseRomError> {
+        let index = self.values.len();
+
+        let (start, end, line) = match self.token.take() {
+            Some(token) => token,
+            None => {
+                return Err(ParseRomError {
+                    index,
+                    line: self.line,
+                    offset,
+                    kind: ParseRomErrorKind::MissingValue,
+                })
+            }
+        };
+
+        let token = &s[start..end];
+        match token.parse::<i64>() {
+            Ok(val) => {
+                self.values.push(val);
+                Ok(())
+            }
+            Err(e) => Err(ParseRomError {
+                index,
+                line,
+                offset: start,
+                kind: ParseRomErrorKind::InvalidValue(token.to_string(), e),
+            }),
+        }
+    }
+}
+
+const MAGIC: &[u8; 3] = b"ICR";
+const VERSION: u8 = 1;
+
+fn zigzag_encode(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+fn zigzag_decode(n: u64) -> i64 {
+    ((n >> 1) as i64) ^ -((n & 1) as i64)
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn read_varint<'a, I>(bytes: &mut I) -> Result<u64, Error>
+where
+    I: Iterator<Item = &'a u8>,
+{
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = match bytes.next() {
+            Some(byte) => *byte,
+            None => bail!("Binary ROM ended in the middle of a varint."),
+        };
+        // The tenth byte may only contribute the single remaining bit.
+        if shift == 63 && byte > 1 {
+            bail!("Varint in binary ROM overflows 64 bits.");
+        }
+        // A zero final byte could have been left off, so only the shortest
+        // encoding of each value is accepted.
+        if byte == 0 && shift > 0 {
+            bail!("Overlong varint in binary ROM.");
+        }
+        n |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    bail!("Varint in binary ROM overflows 64 bits.")
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65_521;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest chunk for which `b` can't overflow before reducing.
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Error returned when the text of a `Rom` is malformed, recording where in
+/// the input the problem was found.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseRomError {
+    index: usize,
+    line: usize,
+    offset: usize,
+    kind: ParseRomErrorKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseRomErrorKind {
+    /// Two commas with nothing between them, or an input with no values.
+    MissingValue,
+    /// A token that is not a valid `i64`.
+    InvalidValue(String, ParseIntError),
+}
+
+impl ParseRomError {
+    /// Index of the value that failed to parse.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Line (starting at 1) on which the bad token begins.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Byte offset into the input at which the bad token begins.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn kind(&self) -> &ParseRomErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for ParseRomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ParseRomErrorKind::MissingValue => write!(
+                f,
+                "Missing value at index {} (line {}, byte offset {}).",
+                self.index, self.line, self.offset
+            ),
+            ParseRomErrorKind::InvalidValue(token, e) => write!(
+                f,
+                "Invalid value {:?} at index {} (line {}, byte offset {}): {}",
+                token, self.index, self.line, self.offset, e
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseRomError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ParseRomErrorKind::MissingValue => None,
+            ParseRomErrorKind::InvalidValue(_, e) => Some(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rom() {
+        let test_cases = &[
+            // (input, expected)
+            ("1,9,10,3", &[1i64, 9, 10, 3][..]),
+            ("1,9,10,3\n", &[1, 9, 10, 3][..]),
+            ("1, 9,\n10 ,3,\n", &[1, 9, 10, 3][..]),
+            (
+                "# header\n1,9, # noun\n10, # verb\n3 # end",
+                &[1, 9, 10, 3][..],
+            ),
+            ("-1,\r\n+2", &[-1, 2][..]),
+        ];
+
+        for (input, expected) in test_cases {
+            let rom = input.parse::<Rom>().unwrap();
+            assert_eq!(&rom[..], *expected);
+        }
+    }
+
+    #[test]
+    fn test_rom_display() {
+        let input = "# header\n1, -9,\n10,3,\n";
+        let rom = input.parse::<Rom>().unwrap();
+        assert_eq!(rom.to_string(), "1,-9,10,3");
+        assert_eq!(&rom.to_string().parse::<Rom>().unwrap()[..], &rom[..]);
+    }
+
+    #[test]
+    fn test_rom_binary() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+
+        for &n in &[0, 1, -1, 63, -64, i64::MAX, i64::MIN] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, zigzag_encode(n));
+            let decoded = zigzag_decode(read_varint(&mut buf.iter()).unwrap());
+            assert_eq!(decoded, n);
+        }
+
+        let file = std::fs::File::open("input/day13.txt").unwrap();
+        let mut rom = Rom::from_reader(std::io::BufReader::new(file)).unwrap();
+        rom[0] = 2;
+        rom.0.extend_from_slice(&[i64::MAX, i64::MIN]);
+
+        let mut buf = Vec::new();
+        rom.write_binary(&mut buf).unwrap();
+        assert!(buf.len() < rom.to_string().len());
+        let actual = Rom::read_binary(&buf[..]).unwrap();
+        assert_eq!(&actual[..], &rom[..]);
+
+        // Corrupting any byte is caught by the checksum
+        buf[10] ^= 0x40;
+        assert!(Rom::read_binary(&buf[..]).is_err());
+        buf[10] ^= 0x40;
+
+        // Truncation is caught
+        assert!(Rom::read_binary(&buf[..buf.len() - 1]).is_err());
+        assert!(Rom::read_binary(&b"ICR"[..]).is_err());
+
+        // Only the shortest encoding of a varint is accepted
+        assert!(read_varint(&mut [0x80, 0x00].iter()).is_err());
+    }
+
+    #[test]
+    fn test_rom_errors() {
+        let test_cases = &[
+            // (input, index, line, offset)
+            ("1,2,x,4", 2, 1, 4),
+            ("1,2\n,3\n,4a", 3, 3, 8),
+            ("1,,3", 1, 1, 2),
+            ("1 2,3", 0, 1, 0),
+            ("# only a comment\n", 0, 2, 17),
+            ("", 0, 1, 0),
+            (",", 0, 1, 0),
+        ];
+
+        for (input, index, line, offset) in test_cases {
+            let e = input.parse::<Rom>().unwrap_err();
+            assert_eq!(e.index(), *index, "{:?}", input);
+            assert_eq!(e.line(), *line, "{:?}", input);
+            assert_eq!(e.offset(), *offset, "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::any::Any;
+use std::io::BufRead;
+
+use crate::error::Error;
+
+/// Parsed puzzle input, shared between the two parts of a `Solution`.
+pub type Parsed = Box<dyn Any>;
+
+/// A solver for one day's puzzle.
+pub trait Solution: Sync {
+    /// Day of the advent calendar, from 1 to 25.
+    fn day(&self) -> usize;
+
+    /// Title of the puzzle.
+    fn title(&self) -> &'static str;
+
+    /// Parses `input` into the form both parts work from.
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Parsed, Error>;
+
+    fn part_one(&self, parsed: &Parsed) -> Result<String, Error>;
+
+    fn part_two(&self, parsed: &Parsed) -> Result<String, Error>;
+
+    /// Solves a single part, numbered 1 or 2.
+    fn part(&self, part: usize, parsed: &Parsed) -> Result<String, Error> {
+        match part {
+            1 => self.part_one(parsed),
+            2 => self.part_two(parsed),
+            _ => bail!("Part must be 1 or 2, but got {}.", part),
+        }
+    }
+
+    /// Solves both parts of the puzzle for `input`.
+    fn run(&self, input: &mut dyn BufRead) -> Result<(String, String), Error> {
+        let parsed = self.parse(input)?;
+        Ok((self.part_one(&parsed)?, self.part_two(&parsed)?))
+    }
+}
+
+type ParseFn = fn(&mut dyn BufRead) -> Result<Parsed, Error>;
+type PartFn = fn(&Parsed) -> Result<String, Error>;
+
+struct Day {
+    day: usize,
+    title: &'static str,
+    parse: ParseFn,
+    part_one: PartFn,
+    part_two: PartFn,
+}
+
+impl Solution for Day {
+    fn day(&self) -> usize {
+        self.day
+    }
+
+    fn title(&self) -> &'static str {
+        self.title
+    }
+
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Parsed, Error> {
+        (self.parse)(input)
+    }
+
+    fn part_one(&self, parsed: &Parsed) -> Result<String, Error> {
+        (self.part_one)(parsed)
+    }
+
+    fn part_two(&self, parsed: &Parsed) -> Result<String, Error> {
+        (self.part_two)(parsed)
+    }
+}
+
+fn downcast<T>(parsed: &Parsed) -> Result<&T, Error>
+where
+    T: 'static,
+{
+    parsed
+        .downcast_ref()
+        .ok_or_else(|| error!("Parsed input was produced by a different day."))
+}
+
+/// Registers days whose modules provide `Input`, `parse`, `part_one` and
+/// `part_two`.
+macro_rules! register {
+    ($($day:expr => $module:ident, $title:expr;)*) => {
+        static SOLUTIONS: &[Day] = &[
+            $(
+                Day {
+                    day: $day,
+                    title: $title,
+                    parse: |input| Ok(Box::new(crate::$module::parse(input)?)),
+                    part_one: |parsed| {
+                        crate::$module::part_one(downcast::<crate::$module::Input>(parsed)?)
+                    },
+                    part_two: |parsed| {
+                        crate::$module::part_two(downcast::<crate::$module::Input>(parsed)?)
+                    },
+                },
+            )*
+        ];
+    };
+}
+
+// Adding a day means declaring its module in `lib.rs` and adding it here.
+register! {
+    1 => day01, "The Tyranny of the Rocket Equation";
+    2 => day02, "1202 Program Alarm";
+    3 => day03, "Crossed Wires";
+    4 => day04, "Secure Container";
+    5 => day05, "Sunny with a Chance of Asteroids";
+    6 => day06, "Universal Orbit Map";
+    7 => day07, "Amplification Circuit";
+    8 => day08, "Space Image Format";
+    9 => day09, "Sensor Boost";
+    10 => day10, "Monitoring Station";
+    11 => day11, "Space Police";
+    12 => day12, "The N-Body Problem";
+    13 => day13, "Care Package";
+    14 => day14, "Space Stoichiometry";
+    15 => day15, "Oxygen System";
+    16 => day16, "Flawed Frequency Transmission";
+    17 => day17, "Set and Forget";
+    18 => day18, "Many-Worlds Interpretation";
+    19 => day19, "Tractor Beam";
+    20 => day20, "Donut Maze";
+    21 => day21, "Springdroid Adventure";
+    22 => day22, "Slam Shuffle";
+    23 => day23, "Category Six";
+    24 => day24, "Planet of Discord";
+}
+
+/// Every implemented solution, in order of day.
+pub fn solutions() -> impl Iterator<Item = &'static dyn Solution> {
+    SOLUTIONS.iter().map(|day| day as &dyn Solution)
+}
+
+/// Looks up the solution for `day`, erroring if it is out of range or not
+/// yet implemented.
+pub fn solution(day: usize) -> Result<&'static dyn Solution, Error> {
+    match solutions().find(|solution| solution.day() == day) {
+        Some(solution) => Ok(solution),
+        None if day > 0 && day < 26 => bail!("Day {} is not yet implemented", day),
+        None => bail!("Day must be between 1 and 25, inclusive."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solutions() {
+        let days = solutions().map(|s| s.day()).collect::<Vec<_>>();
+        let mut sorted = days.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(days, sorted);
+
+        for s in solutions() {
+            assert_eq!(solution(s.day()).unwrap().day(), s.day());
+            assert!(!s.title().is_empty());
+            let path = format!("input/day{:02}.txt", s.day());
+            assert!(std::path::Path::new(&path).exists(), "{}", path);
+        }
+
+        let parsed = solution(1).unwrap().parse(&mut "12".as_bytes()).unwrap();
+        assert!(solution(2).unwrap().part_one(&parsed).is_err());
+        assert!(solution(1).unwrap().part(3, &parsed).is_err());
+
+        assert!(solution(0).is_err());
+        assert!(solution(26).is_err());
+    }
+}
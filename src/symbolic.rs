@@ -0,0 +1,629 @@
+//! Symbolic execution of straight-line Intcode.
+//!
+//! Some programs (like day 2's) take no input and only ever jump on constant
+//! conditions, so the contents of memory when they halt are polynomials in the
+//! values they were patched with. Evaluating the program once symbolically
+//! turns "which inputs produce X?" into an equation that can be solved
+//! directly instead of by running the program for every candidate input.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::computer::{Mode, Modes, MAX_RAM};
+use crate::error::Error;
+use crate::patch::Patch;
+
+/// Instructions executed before giving up on a program that doesn't halt.
+const LIMIT: u64 = 1_000_000;
+
+/// A polynomial with integer coefficients whose variables are the original
+/// contents of memory addresses.
+///
+/// Each term is keyed by the sorted list of addresses multiplied together, so
+/// `3 * [1] * [1] * [2]` is stored as `[1, 1, 2] => 3` and a constant is
+/// stored under the empty key.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Expr(BTreeMap<Vec<u64>, i64>);
+
+impl Expr {
+    pub fn constant(val: i64) -> Self {
+        let mut terms = BTreeMap::new();
+        if val != 0 {
+            terms.insert(Vec::new(), val);
+        }
+        Self(terms)
+    }
+
+    pub fn var(ptr: u64) -> Self {
+        let mut terms = BTreeMap::new();
+        terms.insert(vec![ptr], 1);
+        Self(terms)
+    }
+
+    /// Returns the value of the expression if it doesn't depend on any
+    /// variables.
+    pub fn as_constant(&self) -> Option<i64> {
+        match self.0.len() {
+            0 => Some(0),
+            1 => self.0.get(&Vec::new()).copied(),
+            _ => None,
+        }
+    }
+
+    /// Returns the constant term and the coefficient of each variable if the
+    /// expression is linear.
+    pub fn as_linear(&self) -> Option<(i64, Vec<(u64, i64)>)> {
+        let mut constant = 0;
+        let mut coefficients = Vec::new();
+        for (vars, &coefficient) in &self.0 {
+            match vars.len() {
+                0 => constant = coefficient,
+                1 => coefficients.push((vars[0], coefficient)),
+                _ => return None,
+            }
+        }
+        Some((constant, coefficients))
+    }
+
+    /// Addresses the expression depends on.
+    pub fn vars(&self) -> Vec<u64> {
+        let mut vars = self.0.keys().flatten().copied().collect::<Vec<_>>();
+        vars.sort();
+        vars.dedup();
+        vars
+    }
+
+    /// Evaluates the expression, looking up each variable with `f`.
+    pub fn eval<F>(&self, f: F) -> Result<i64, Error>
+    where
+        F: Fn(u64) -> Option<i64>,
+    {
+        let mut total = 0i64;
+        for (vars, &coefficient) in &self.0 {
+            let mut term = coefficient;
+            for &ptr in vars {
+                let val = f(ptr).ok_or_else(|| error!("No value for variable [{}].", ptr))?;
+                term = term
+                    .checked_mul(val)
+                    .ok_or_else(|| error!("Overflow evaluating {}.", self))?;
+            }
+            total = total
+                .checked_add(term)
+                .ok_or_else(|| error!("Overflow evaluating {}.", self))?;
+        }
+        Ok(total)
+    }
+
+    fn add(&self, other: &Expr) -> Result<Expr, Error> {
+        let mut terms = self.0.clone();
+        for (vars, &coefficient) in &other.0 {
+            let entry = terms.entry(vars.clone()).or_insert(0);
+            *entry = entry
+                .checked_add(coefficient)
+                .ok_or_else(|| error!("Overflow adding {} and {}.", self, other))?;
+            if *entry == 0 {
+                terms.remove(vars);
+            }
+        }
+        Ok(Expr(terms))
+    }
+
+    fn mul(&self, other: &Expr) -> Result<Expr, Error> {
+        let mut output = Expr::default();
+        for (vars_a, &a) in &self.0 {
+            for (vars_b, &b) in &other.0 {
+                let mut vars = vars_a.clone();
+                vars.extend_from_slice(vars_b);
+                vars.sort();
+
+                let coefficient = a
+                    .checked_mul(b)
+                    .ok_or_else(|| error!("Overflow multiplying {} and {}.", self, other))?;
+                let mut term = BTreeMap::new();
+                term.insert(vars, coefficient);
+                output = output.add(&Expr(term))?;
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "0");
+        }
+
+        // The constant sorts first, but reads better at the end.
+        let terms = self.0.iter().filter(|(vars, _)| !vars.is_empty());
+        let constant = self.0.iter().filter(|(vars, _)| vars.is_empty());
+        for (i, (vars, coefficient)) in terms.chain(constant).enumerate() {
+            let mut coefficient = *coefficient;
+            if i > 0 {
+                if coefficient < 0 {
+                    write!(f, " - ")?;
+                    coefficient = -coefficient;
+                } else {
+                    write!(f, " + ")?;
+                }
+            }
+
+            if vars.is_empty() {
+                write!(f, "{}", coefficient)?;
+                continue;
+            }
+            match coefficient {
+                1 => (),
+                -1 => write!(f, "-")?,
+                _ => write!(f, "{}*", coefficient)?,
+            }
+            for (j, ptr) in vars.iter().enumerate() {
+                if j > 0 {
+                    write!(f, "*")?;
+                }
+                write!(f, "[{}]", ptr)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An Intcode computer whose memory holds `Expr`s rather than values.
+///
+/// Execution fails if the program needs input, produces output, or if
+/// anything that affects control flow depends on a variable: an opcode, a
+/// jump, the relative base, or the address being written to.
+///
+/// Reading through a pointer that depends on a variable is allowed, but the
+/// value read is unknown. Unknown values may be overwritten or ignored
+/// (day 2's first instruction reads through its noun and verb, then the
+/// result is clobbered), and only cause an error if something above uses them.
+#[derive(Clone, Debug)]
+pub struct SymbolicComputer {
+    pc: u64,
+    rb: i64,
+    /// `None` marks a cell whose contents are unknown.
+    ram: Vec<Option<Expr>>,
+}
+
+impl SymbolicComputer {
+    /// Loads `rom`, replacing the contents of each address in `vars` with a
+    /// variable named after that address.
+    pub fn new<R>(rom: R, vars: &[u64]) -> Result<Self, Error>
+    where
+        R: AsRef<[i64]>,
+    {
+        let mut computer = Self {
+            pc: 0,
+            rb: 0,
+            ram: rom
+                .as_ref()
+                .iter()
+                .map(|&val| Some(Expr::constant(val)))
+                .collect(),
+        };
+        for &ptr in vars {
+            computer.write(ptr, Some(Expr::var(ptr)))?;
+        }
+        Ok(computer)
+    }
+
+    pub fn run(&mut self) -> Result<(), Error> {
+        for _ in 0..LIMIT {
+            let opcode = self.constant_at(self.pc, "opcode")?;
+            self.pc += 1;
+            if opcode < 0 {
+                bail!("Read negative opcode {}, which is not allowed", opcode);
+            }
+            let mut modes = Modes(opcode as u64 / 100);
+
+            match opcode % 100 {
+                1 | 2 => {
+                    let a = self.read_param(&mut modes)?;
+                    let b = self.read_param(&mut modes)?;
+                    let w = self.read_ptr(&mut modes)?;
+                    let val = match (a, b) {
+                        (Some(a), Some(b)) if opcode % 100 == 1 => Some(a.add(&b)?),
+                        (Some(a), Some(b)) => Some(a.mul(&b)?),
+                        _ => None,
+                    };
+                    self.write(w, val)?;
+                }
+                3 | 4 => bail!(
+                    "Symbolic execution does not support I/O (opcode {} at {}).",
+                    opcode,
+                    self.pc - 1
+                ),
+                5 | 6 => {
+                    let a = self.read_constant_param(&mut modes, "jump condition")?;
+                    let p = self.read_constant_param(&mut modes, "jump target")?;
+                    if (opcode % 100 == 5) == (a != 0) {
+                        if p < 0 {
+                            bail!("Cannot jump to negative address {}.", p);
+                        }
+                        self.pc = p as u64;
+                    }
+                }
+                7 | 8 => {
+                    let a = self.read_param(&mut modes)?;
+                    let b = self.read_param(&mut modes)?;
+                    let w = self.read_ptr(&mut modes)?;
+                    let constants = (
+                        a.as_ref().and_then(Expr::as_constant),
+                        b.as_ref().and_then(Expr::as_constant),
+                    );
+                    let val = match constants {
+                        (Some(a), Some(b)) if opcode % 100 == 7 => Some(a < b),
+                        (Some(a), Some(b)) => Some(a == b),
+                        // Identical expressions are equal whatever the variables
+                        _ if opcode % 100 == 8 && a.is_some() && a == b => Some(true),
+                        _ => None,
+                    };
+                    self.write(w, val.map(|val| Expr::constant(val as i64)))?;
+                }
+                9 => {
+                    let a = self.read_constant_param(&mut modes, "relative base")?;
+                    self.rb = self
+                        .rb
+                        .checked_add(a)
+                        .ok_or_else(|| error!("Overflow adjusting relative base."))?;
+                }
+                99 => return Ok(()),
+                _ => bail!("Unrecognized opcode {}", opcode),
+            }
+        }
+
+        bail!("Program did not halt within {} instructions.", LIMIT)
+    }
+
+    /// Returns the contents of `ptr`, or `None` if they are unknown.
+    pub fn read(&self, ptr: u64) -> Option<Expr> {
+        match self.ram.get(ptr as usize) {
+            Some(cell) => cell.clone(),
+            None => Some(Expr::default()),
+        }
+    }
+
+    fn write(&mut self, ptr: u64, val: Option<Expr>) -> Result<(), Error> {
+        if ptr >= MAX_RAM {
+            bail!(
+                "Attempted to write to address {}, which exceeds the maximum of {}.",
+                ptr,
+                MAX_RAM - 1
+            );
+        }
+        let ptr = ptr as usize;
+        if ptr >= self.ram.len() {
+            self.ram.resize(ptr + 1, Some(Expr::default()));
+        }
+        self.ram[ptr] = val;
+        Ok(())
+    }
+
+    fn constant_at(&self, ptr: u64, what: &str) -> Result<i64, Error> {
+        match self.read(ptr) {
+            Some(expr) => expr
+                .as_constant()
+                .ok_or_else(|| error!("The {} at address {} depends on {}.", what, ptr, expr)),
+            None => bail!("The {} at address {} is unknown.", what, ptr),
+        }
+    }
+
+    /// Resolves a pointer according to its addressing mode.
+    fn resolve(&self, val: i64, mode: Mode) -> Result<u64, Error> {
+        let ptr = match mode {
+            Mode::Immediate | Mode::Position => val,
+            Mode::Relative => val
+                .checked_add(self.rb)
+                .ok_or_else(|| error!("Overflow offsetting {} by relative base.", val))?,
+        };
+        if ptr < 0 {
+            bail!(
+                "Encountered negative pointer {}, which is not allowed.",
+                ptr
+            );
+        }
+        Ok(ptr as u64)
+    }
+
+    fn read_param(&mut self, modes: &mut Modes) -> Result<Option<Expr>, Error> {
+        let raw = self.read(self.pc);
+        self.pc += 1;
+
+        let mode = modes.next_mode()?;
+        if mode == Mode::Immediate {
+            return Ok(raw);
+        }
+
+        // Reading through a pointer we don't know gives a value we don't know.
+        match raw.as_ref().and_then(Expr::as_constant) {
+            Some(val) => Ok(self.read(self.resolve(val, mode)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn read_constant_param(&mut self, modes: &mut Modes, what: &str) -> Result<i64, Error> {
+        let ptr = self.pc;
+        match self.read_param(modes)? {
+            Some(expr) => expr.as_constant().ok_or_else(|| {
+                error!(
+                    "The {} for the instruction at {} depends on {}.",
+                    what, ptr, expr
+                )
+            }),
+            None => bail!("The {} for the instruction at {} is unknown.", what, ptr),
+        }
+    }
+
+    fn read_ptr(&mut self, modes: &mut Modes) -> Result<u64, Error> {
+        let val = self.constant_at(self.pc, "pointer")?;
+        self.pc += 1;
+
+        let mode = modes.next_mode()?;
+        self.resolve(val, mode)
+    }
+}
+
+/// Finds values for the variables of `expr`, each within the range given in
+/// `bounds`, that make it equal `target`. Returns them as a `Patch`.
+///
+/// When there is more than one solution, the one that is smallest comparing
+/// variables in the order of `bounds` is returned. Linear expressions are
+/// solved with the extended Euclidean algorithm; anything else falls back to
+/// evaluating every combination.
+pub fn solve(
+    expr: &Expr,
+    target: i64,
+    bounds: &[(u64, RangeInclusive<i64>)],
+) -> Result<Option<Patch>, Error> {
+    for ptr in expr.vars() {
+        if !bounds.iter().any(|(other, _)| *other == ptr) {
+            bail!("No bounds given for variable [{}] in {}.", ptr, expr);
+        }
+    }
+    if bounds.iter().any(|(_, range)| range.is_empty()) {
+        return Ok(None);
+    }
+
+    let values = match expr.as_linear() {
+        Some((constant, coefficients)) => {
+            let coefficients = bounds
+                .iter()
+                .map(|(ptr, range)| {
+                    let c = coefficients
+                        .iter()
+                        .find(|(other, _)| other == ptr)
+                        .map(|(_, c)| *c)
+                        .unwrap_or(0);
+                    (i128::from(c), range)
+                })
+                .collect::<Vec<_>>();
+            solve_linear(&coefficients, i128::from(target) - i128::from(constant))
+        }
+        None => {
+            let mut values = Vec::with_capacity(bounds.len());
+            solve_exhaustive(expr, target, bounds, &mut values)?
+        }
+    };
+
+    Ok(values.map(|values| Patch::new(bounds.iter().map(|(ptr, _)| *ptr).zip(values))))
+}
+
+/// Solves `sum(c * x) = target` for the lexicographically smallest `x`.
+fn solve_linear(coefficients: &[(i128, &RangeInclusive<i64>)], target: i128) -> Option<Vec<i64>> {
+    match coefficients {
+        [] => {
+            if target == 0 {
+                Some(Vec::new())
+            } else {
+                None
+            }
+        }
+        // A variable that doesn't matter takes its smallest value.
+        [(0, range), rest @ ..] => {
+            let mut values = solve_linear(rest, target)?;
+            values.insert(0, *range.start());
+            Some(values)
+        }
+        [(c, range)] => {
+            if target % c != 0 {
+                return None;
+            }
+            let x = target / c;
+            if x < i128::from(*range.start()) || x > i128::from(*range.end()) {
+                return None;
+            }
+            Some(vec![x as i64])
+        }
+        [(a, range_x), (0, range_y)] => {
+            let mut values = solve_linear(&[(*a, *range_x)], target)?;
+            values.push(*range_y.start());
+            Some(values)
+        }
+        [(a, range_x), (b, range_y)] => {
+            // a*s + b*t = g, so x = s*target/g is one solution and every
+            // other is x + k*b/g, with y following from x.
+            let (g, s, _) = extended_gcd(*a, *b);
+            if target % g != 0 {
+                return None;
+            }
+            let (dx, dy) = (b / g, -a / g);
+
+            // Reduce x modulo b/g first so nothing below can overflow.
+            let m = dx.abs();
+            let x0 = ((s % m) * ((target / g) % m)) % m;
+            let y0 = (target - a * x0) / b;
+
+            let (lo_x, hi_x) = k_range(x0, dx, range_x);
+            let (lo_y, hi_y) = k_range(y0, dy, range_y);
+            let (lo, hi) = (lo_x.max(lo_y), hi_x.min(hi_y));
+            if lo > hi {
+                return None;
+            }
+
+            let k = if dx > 0 { lo } else { hi };
+            Some(vec![(x0 + k * dx) as i64, (y0 + k * dy) as i64])
+        }
+        [(_, range), rest @ ..] => {
+            for x in (*range).clone() {
+                let c = coefficients[0].0;
+                if let Some(mut values) = solve_linear(rest, target - c * i128::from(x)) {
+                    values.insert(0, x);
+                    return Some(values);
+                }
+            }
+            None
+        }
+    }
+}
+
+/// Returns the range of `k` for which `start + k * step` lies in `range`.
+fn k_range(start: i128, step: i128, range: &RangeInclusive<i64>) -> (i128, i128) {
+    let lo = i128::from(*range.start()) - start;
+    let hi = i128::from(*range.end()) - start;
+    if step > 0 {
+        (div_ceil(lo, step), div_floor(hi, step))
+    } else {
+        (div_ceil(hi, step), div_floor(lo, step))
+    }
+}
+
+fn div_floor(a: i128, b: i128) -> i128 {
+    let (q, r) = (a / b, a % b);
+    if r != 0 && ((r < 0) != (b < 0)) {
+        q - 1
+    } else {
+        q
+    }
+}
+
+fn div_ceil(a: i128, b: i128) -> i128 {
+    -div_floor(-a, b)
+}
+
+/// Returns `(g, s, t)` such that `a*s + b*t = g = gcd(a, b)` and `g > 0`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_s, mut s) = (1, 0);
+    let (mut old_t, mut t) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        let tmp = old_r - q * r;
+        old_r = r;
+        r = tmp;
+        let tmp = old_s - q * s;
+        old_s = s;
+        s = tmp;
+        let tmp = old_t - q * t;
+        old_t = t;
+        t = tmp;
+    }
+    if old_r < 0 {
+        (-old_r, -old_s, -old_t)
+    } else {
+        (old_r, old_s, old_t)
+    }
+}
+
+fn solve_exhaustive(
+    expr: &Expr,
+    target: i64,
+    bounds: &[(u64, RangeInclusive<i64>)],
+    values: &mut Vec<i64>,
+) -> Result<Option<Vec<i64>>, Error> {
+    if values.len() == bounds.len() {
+        let lookup = |ptr| {
+            bounds
+                .iter()
+                .position(|(other, _)| *other == ptr)
+                .map(|i| values[i])
+        };
+        // Overflowing candidates can't be equal to an `i64` target.
+        return match expr.eval(lookup) {
+            Ok(val) if val == target => Ok(Some(values.clone())),
+            _ => Ok(None),
+        };
+    }
+
+    for x in bounds[values.len()].1.clone() {
+        values.push(x);
+        if let Some(solution) = solve_exhaustive(expr, target, bounds, values)? {
+            return Ok(Some(solution));
+        }
+        values.pop();
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::rom::Rom;
+
+    #[test]
+    fn test_symbolic() {
+        // [0] = ([1] + [2]) * [1]
+        let rom = "1101,0,0,9,2,9,1,0,99,0".parse::<Rom>().unwrap();
+        let mut computer = SymbolicComputer::new(&rom, &[1, 2]).unwrap();
+        computer.run().unwrap();
+        let expr = computer.read(0).unwrap();
+        assert_eq!(expr.to_string(), "[1]*[1] + [1]*[2]");
+        assert_eq!(expr.as_linear(), None);
+        assert_eq!(expr.vars(), vec![1, 2]);
+
+        let expected = Patch::new(vec![(1, 3), (2, 4)]);
+        let bounds = &[(1, 0..=9), (2, 0..=9)];
+        assert_eq!(solve(&expr, 21, bounds).unwrap(), Some(expected));
+        assert_eq!(solve(&expr, 23, bounds).unwrap(), None);
+
+        // Reading through a variable pointer is fine as long as the result is
+        // overwritten: [3] = [[1]] + [[2]], then [3] = [1] + [2], [0] = 5 * [3]
+        let rom = "1,0,0,3,1,1,2,3,2,3,13,0,99,5".parse::<Rom>().unwrap();
+        let mut computer = SymbolicComputer::new(&rom, &[1, 2]).unwrap();
+        computer.run().unwrap();
+        assert_eq!(computer.read(0).unwrap().to_string(), "5*[1] + 5*[2]");
+
+        // ... but using the result is not
+        let rom = "1,0,0,3,2,3,13,0,99,5".parse::<Rom>().unwrap();
+        let mut computer = SymbolicComputer::new(&rom, &[1, 2]).unwrap();
+        computer.run().unwrap();
+        assert_eq!(computer.read(0), None);
+
+        // Jumping on a variable can't be done symbolically
+        let rom = "1005,1,0,99".parse::<Rom>().unwrap();
+        let mut computer = SymbolicComputer::new(&rom, &[1]).unwrap();
+        assert!(computer.run().is_err());
+    }
+
+    #[test]
+    fn test_solve_linear() {
+        // 7x - 3y + 2z = 30
+        let expr = Expr::var(1)
+            .mul(&Expr::constant(7))
+            .unwrap()
+            .add(&Expr::var(2).mul(&Expr::constant(-3)).unwrap())
+            .unwrap()
+            .add(&Expr::var(3).mul(&Expr::constant(2)).unwrap())
+            .unwrap();
+        assert_eq!(expr.to_string(), "7*[1] - 3*[2] + 2*[3]");
+
+        let bounds = [(1, -5..=5), (2, -5..=5), (3, -5..=5)];
+        let patch = solve(&expr, 30, &bounds).unwrap().unwrap();
+        let lookup = |ptr| patch.edits().iter().find(|e| e.0 == ptr).map(|e| e.1);
+        assert_eq!(expr.eval(lookup).unwrap(), 30);
+
+        // Matches an exhaustive search for the smallest solution
+        for target in -60..=60 {
+            for bounds in &[[(1, 0..=9), (2, 0..=9)], [(2, -4..=4), (1, 3..=7)]] {
+                let expr = expr.add(&Expr::var(3).mul(&Expr::constant(-2)).unwrap());
+                let expr = expr.unwrap();
+                let expected = solve_exhaustive(&expr, target, bounds, &mut Vec::new())
+                    .unwrap()
+                    .map(|values| Patch::new(bounds.iter().map(|b| b.0).zip(values)));
+                assert_eq!(solve(&expr, target, bounds).unwrap(), expected);
+            }
+        }
+    }
+}
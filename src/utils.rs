@@ -41,6 +41,34 @@ pub(crate) mod math {
         Ok(a * b / gcf(a, b)?)
     }
 
+    /// Returns `a * b % m` without overflowing.
+    pub(crate) fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+        (a as u128 * b as u128 % m as u128) as u64
+    }
+
+    /// Returns the `x` in `0..m` with `a * x % m == 1`, using the extended
+    /// Euclidean algorithm. Errors unless `a` and `m` are coprime.
+    pub(crate) fn inv_mod(a: u64, m: u64) -> Result<u64, Error> {
+        if m == 0 {
+            bail!("Modulus must be positive.");
+        }
+
+        let (mut old_r, mut r) = ((a % m) as i128, m as i128);
+        let (mut old_s, mut s) = (1i128, 0i128);
+
+        while r != 0 {
+            let q = old_r / r;
+            (old_r, r) = (r, old_r - q * r);
+            (old_s, s) = (s, old_s - q * s);
+        }
+
+        if old_r != 1 && m != 1 {
+            bail!("{} has no inverse modulo {}.", a, m);
+        }
+
+        Ok(old_s.rem_euclid(m as i128) as u64)
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -53,6 +81,20 @@ pub(crate) mod math {
             assert!(gcf(1, 0).is_err());
         }
 
+        #[test]
+        fn test_modular() {
+            assert_eq!(mul_mod(u64::MAX, u64::MAX, 1_000_000_007), 114_944_269);
+            assert_eq!(inv_mod(3, 7).unwrap(), 5);
+            assert_eq!(inv_mod(7, 10).unwrap(), 3);
+            assert_eq!(inv_mod(10, 7).unwrap(), 5);
+            assert!(inv_mod(4, 10).is_err());
+            assert!(inv_mod(0, 7).is_err());
+
+            let m = 119_315_717_514_047;
+            let a = 101_741_582_076_661;
+            assert_eq!(mul_mod(a, inv_mod(a, m).unwrap(), m), 1);
+        }
+
         #[test]
         fn test_factorial() {
             assert_eq!(fact(5).unwrap(), 120);
@@ -194,10 +236,7 @@ impl<T> From<[T; 3]> for Vec3<T> {
     }
 }
 
-#[cfg(all(
-    any(target_arch = "x86", target_arch = "x86_64"),
-    target_feature = "avx2"
-))]
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 mod simd {
     use super::*;
 
@@ -208,11 +247,9 @@ mod simd {
 
     impl From<__m256i> for Vec3<i64> {
         fn from(v: __m256i) -> Self {
-            let mut a: [i64; 4] = unsafe { std::mem::MaybeUninit::uninit().assume_init() };
-            #[allow(clippy::cast_ptr_alignment)]
-            unsafe {
-                _mm256_storeu_si256(&mut a as *mut _ as *mut __m256i, v)
-            };
+            // Safety: `__m256i` and `[i64; 4]` have the same size, and every
+            // bit pattern is a valid `i64`.
+            let a: [i64; 4] = unsafe { std::mem::transmute(v) };
             Vec3::new(a[3], a[2], a[1])
         }
     }
@@ -220,6 +257,7 @@ mod simd {
 
 #[cfg(test)]
 pub(crate) mod tests {
+    use crate::answers::Answers;
     use crate::error::Error;
 
     pub(crate) fn test_full_problem<F>(day: usize, run_func: F, expected1: &str, expected2: &str)
@@ -233,4 +271,25 @@ pub(crate) mod tests {
         assert_eq!(&actual1, expected1);
         assert_eq!(&actual2, expected2);
     }
+
+    /// Checks every input for `day` listed in `input/answers.txt`.
+    pub(crate) fn test_answers(day: usize) {
+        let file = std::fs::File::open("input/answers.txt").unwrap();
+        let reader = std::io::BufReader::new(file);
+        let answers = Answers::from_reader(reader).unwrap();
+
+        let checks = answers.check("input", Some(day));
+        assert!(!checks.is_empty(), "No answers for day {}.", day);
+
+        for check in checks {
+            assert!(
+                check.passed(),
+                "{} part {}: expected {:?}, got {:?}",
+                check.name,
+                check.part,
+                check.expected,
+                check.actual
+            );
+        }
+    }
 }
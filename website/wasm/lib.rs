@@ -12,9 +12,9 @@ impl Game {
     pub fn new() -> Result<Game, JsValue> {
         console_error_panic_hook::set_once();
         let bytes = include_bytes!("../../input/day13.txt");
-        let reader = std::io::BufReader::new(&bytes[..]);
+        let reader = aoc2019::Reader::from(&bytes[..]);
         let rom = aoc2019::Rom::from_reader(reader).map_err(|e| e.to_string())?;
-        let game = aoc2019::Game::new(&rom);
+        let game = aoc2019::Game::new(&rom).map_err(|e| e.to_string())?;
 
         Ok(Self(game))
     }
@@ -49,3 +49,51 @@ impl Game {
         self.0.score()
     }
 }
+
+/// Days with a solution, in order.
+#[wasm_bindgen]
+pub fn days() -> Box<[u32]> {
+    aoc2019::solutions()
+        .map(|solution| solution.day() as u32)
+        .collect()
+}
+
+/// Title of the puzzle for `day`.
+#[wasm_bindgen]
+pub fn title(day: usize) -> Result<String, JsValue> {
+    let solution = aoc2019::solution(day).map_err(|e| e.to_string())?;
+
+    Ok(solution.title().to_string())
+}
+
+/// Solves both parts of `day` for `input`, returning the two answers.
+#[wasm_bindgen]
+pub fn solve(day: usize, input: &str) -> Result<Box<[JsValue]>, JsValue> {
+    console_error_panic_hook::set_once();
+    let solution = aoc2019::solution(day).map_err(|e| e.to_string())?;
+    let mut reader = aoc2019::Reader::from(input);
+    let (answer1, answer2) = solution.run(&mut reader).map_err(|e| e.to_string())?;
+
+    Ok(vec![JsValue::from(answer1), JsValue::from(answer2)].into_boxed_slice())
+}
+
+/// Solves both parts of `day` for `input` after patching its Intcode ROM
+/// with `patches`, the text of a patch file. `name` selects a single patch
+/// from the file; otherwise every patch in it is applied.
+#[wasm_bindgen]
+pub fn solve_patched(
+    day: usize,
+    input: &str,
+    patches: &str,
+    name: Option<String>,
+) -> Result<Box<[JsValue]>, JsValue> {
+    console_error_panic_hook::set_once();
+    let set = aoc2019::PatchSet::from_reader(aoc2019::Reader::from(patches))
+        .map_err(|e| e.to_string())?;
+    let patch = set.select(name.as_deref()).map_err(|e| e.to_string())?;
+    let patched = patch
+        .apply_to_text(aoc2019::Reader::from(input))
+        .map_err(|e| e.to_string())?;
+
+    solve(day, &patched)
+}